@@ -22,10 +22,14 @@ pub use crate::error::BrowserError;
 use super::extension;
 
 
+/// Ответ сервиса внешнего IP: формат api.myip.com или ip-api.com
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct MyIP {
+    #[serde(alias = "query")]
     pub ip: String,
+    #[serde(default)]
     pub country: String,
+    #[serde(default, alias = "countryCode")]
     pub cc: String,
 }
 
@@ -40,6 +44,37 @@ pub static DEFAULT_ARGS: [&str; 8] = [
     "--disable-features=TranslateUI"
 ];
 
+/// Классификация загруженной страницы
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PageStatus {
+    #[default]
+    Ok,
+    Blocked,
+    Captcha,
+    NotFound,
+}
+
+impl PageStatus {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Self::Ok)
+    }
+
+    /// Страница антибот защиты: запросы с текущего IP блокируются
+    pub fn is_blocked(&self) -> bool {
+        matches!(self, Self::Blocked | Self::Captcha)
+    }
+
+    /// Классификация по HTTP статусу ответа
+    pub fn from_http_status(status: u16) -> Self {
+        match status {
+            404 | 410 => Self::NotFound,
+            401 | 403 | 429 | 498 => Self::Blocked,
+            _ => Self::Ok,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct BrowserTimings {
     pub launch_sleep: u64,
@@ -254,6 +289,13 @@ impl BrowserSession {
     }
 
     pub async fn open_with_param<'a>(&self, url: &str, param: &PageParam<'a>) -> Result<Page, BrowserError> {
+        self.open_with_status(url, param).await.map(|(page, _)| page)
+    }
+
+    /// Открывает страницу как `open_with_param` и классифицирует ее.
+    /// Появление элемента `until_selector` из `wait_for_el_until` вместо
+    /// ожидаемого элемента означает страницу капчи.
+    pub async fn open_with_status<'a>(&self, url: &str, param: &PageParam<'a>) -> Result<(Page, PageStatus), BrowserError> {
         if let Some(proxy) = param.proxy {
             self.set_proxy(proxy).await?;
         }
@@ -297,13 +339,16 @@ impl BrowserSession {
                 selector, timeout
             ).await;
         }
+        let mut status = PageStatus::Ok;
         if let Some((selector, until_selector, timeout)) = param.wait_for_el_until {
-            let _ = page.wait_for_el_until_with_timeout(
+            if let Ok(false) = page.wait_for_el_until_with_timeout(
                 selector, until_selector, timeout
-            ).await;
+            ).await {
+                status = PageStatus::Captcha;
+            }
         }
 
-        Ok(page)
+        Ok((page, status))
     }
 
     pub async fn set_proxy(&self, proxy: &str) -> Result<(), BrowserError> {
@@ -367,7 +412,11 @@ impl BrowserSession {
     }
 
     pub async fn myip(&self) -> Result<MyIP, BrowserError> {
-        let page = self.open("https://api.myip.com/").await?;
+        self.myip_from("https://api.myip.com/").await
+    }
+
+    pub async fn myip_from(&self, url: &str) -> Result<MyIP, BrowserError> {
+        let page = self.open(url).await?;
         let myip = page.find_element("body").await?
             .inner_text().await?
             .ok_or(BrowserError::Serialization)
@@ -385,11 +434,12 @@ pub trait Wait {
 
     async fn wait_for_el(&self, selector: &str);
 
-    async fn wait_for_el_until(&self, selector: &str, until_selector: &str);
+    /// Возвращает `false`, если ожидание прервано элементом `until_selector`
+    async fn wait_for_el_until(&self, selector: &str, until_selector: &str) -> bool;
 
     async fn wait_for_el_with_timeout(&self, selector: &str, t: u64) -> Result<(), BrowserError>;
 
-    async fn wait_for_el_until_with_timeout(&self, selector: &str, until_selector: &str, t: u64) -> Result<(), BrowserError>;
+    async fn wait_for_el_until_with_timeout(&self, selector: &str, until_selector: &str, t: u64) -> Result<bool, BrowserError>;
 }
 
 impl Wait for Page {
@@ -401,18 +451,19 @@ impl Wait for Page {
         }
     }
 
-    async fn wait_for_el_until(&self, selector: &str, until_selector: &str) {
+    async fn wait_for_el_until(&self, selector: &str, until_selector: &str) -> bool {
         while self.find_element(selector).await.is_err() {
             sleep(
                 Duration::from_millis(Self::WAIT_SLEEP)
             ).await;
             if self.find_element(until_selector).await.is_ok() {
-                break;
+                return false;
             }
             sleep(
                 Duration::from_millis(Self::WAIT_SLEEP)
             ).await;
         }
+        true
     }
 
     async fn wait_for_el_with_timeout(
@@ -428,13 +479,13 @@ impl Wait for Page {
 
     async fn wait_for_el_until_with_timeout(
         &self, selector: &str, until_selector: &str, t: u64
-    ) -> Result<(), BrowserError> {
-        timeout(
+    ) -> Result<bool, BrowserError> {
+        let found = timeout(
             Duration::from_millis(t),
             self.wait_for_el_until(selector, until_selector)
         ).await?;
 
-        Ok(())
+        Ok(found)
     }
 }
 
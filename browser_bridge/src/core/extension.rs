@@ -36,10 +36,10 @@ pub static PATH: Lazy<String> = Lazy::new(|| {
 pub fn init() -> std::io::Result<PathBuf> {
     let current_dir = get_current_dir()?;
     let extension_path = current_dir.join("extension");
-    if extension_path.exists() {
-        return Ok(extension_path);
+    // Файлы перезаписываются, чтобы расширение обновлялось вместе с приложением
+    if !extension_path.exists() {
+        create_dir(&extension_path)?;
     }
-    create_dir(&extension_path)?;
     let background_path = extension_path.join("background.js");
     let manifest_path = extension_path.join("manifest.json");
     write_to_file(background_path, BACKGROUND_JS)?;
@@ -80,6 +80,19 @@ const CHROME_URLS = {
     CLOSE_TABS: 'chrome://close_tabs',
 };
 
+// Proxy URL schemes and Chrome proxy schemes (DNS is always resolved by SOCKS5 proxy)
+const PROXY_SCHEMES = {
+    http: 'http',
+    https: 'https',
+    socks5: 'socks5',
+    socks5h: 'socks5',
+};
+
+const DEFAULT_PORTS = {
+    http: '80',
+    https: '443',
+};
+
 // Proxy configuration handler
 class ProxyManager {
     constructor() {
@@ -96,29 +109,21 @@ class ProxyManager {
 
         // Extract parameters from query string
         const queryParams = {
+            scheme: searchParams.get('scheme'),
             host: searchParams.get('host'),
             port: searchParams.get('port') ? parseInt(searchParams.get('port'), 10) : null,
             username: searchParams.get('username'),
             password: searchParams.get('password')
         };
 
-        // Updated regex for parsing path
-        const proxyRegex = /^\/([^:]+):([^@]+)@([^:]+):(\d+)\/?$/;
-        const pathMatch = parsedUrl.pathname.match(proxyRegex);
-
         console.log('pathnameUrl:', parsedUrl.pathname);
-        console.log('pathMatch:', pathMatch);
 
-        // Extract data from path if match exists
-        const pathParams = pathMatch ? {
-            username: pathMatch[1] || null,
-            password: pathMatch[2] || null,
-            host: pathMatch[3],
-            port: parseInt(pathMatch[4], 10)
-        } : {};
+        // Path is a proxy URL: [scheme://][username:password@]host:port
+        const pathParams = this.parseProxyUrl(parsedUrl.pathname.replace(/^\/+/, '')) || {};
 
         // Priority to query parameters
         const config = {
+            scheme: queryParams.scheme || pathParams.scheme || 'http',
             host: queryParams.host || pathParams.host,
             port: queryParams.port || pathParams.port,
             username: queryParams.username || pathParams.username,
@@ -126,7 +131,35 @@ class ProxyManager {
         };
 
         // Validate mandatory fields
-        return config.host && config.port && !isNaN(config.port) ? config : null;
+        return config.host && config.port && !isNaN(config.port) && PROXY_SCHEMES[config.scheme]
+            ? config
+            : null;
+    }
+
+    /**
+     * Parse proxy URL (IPv6 hosts in brackets, credentials are percent-encoded)
+     * @param {string} value - Proxy URL with or without scheme
+     * @returns {Object|null} Parsed proxy configuration
+     */
+    parseProxyUrl(value) {
+        if (!value) {
+            return null;
+        }
+        try {
+            const raw = value.replace(/\/+$/, '');
+            const proxyUrl = new URL(raw.includes('://') ? raw : `http://${raw}`);
+            const scheme = proxyUrl.protocol.replace(/:$/, '');
+            return {
+                scheme,
+                host: proxyUrl.hostname,
+                port: parseInt(proxyUrl.port || DEFAULT_PORTS[scheme], 10),
+                username: proxyUrl.username ? decodeURIComponent(proxyUrl.username) : null,
+                password: proxyUrl.password ? decodeURIComponent(proxyUrl.password) : null
+            };
+        } catch (error) {
+            console.error('Proxy URL parse error:', error);
+            return null;
+        }
     }
 
     /**
@@ -162,7 +195,7 @@ class ProxyManager {
                 mode: 'fixed_servers',
                 rules: {
                     singleProxy: {
-                        scheme: 'http',
+                        scheme: PROXY_SCHEMES[proxyConfig.scheme],
                         host: proxyConfig.host,
                         port: proxyConfig.port,
                     },
@@ -193,7 +226,9 @@ class ProxyManager {
         return (details) => {
             console.log('Authentication request details:', details);
 
-            if (details.challenger && details.challenger.host !== proxyConfig.host) {
+            // Chrome does not request credentials for SOCKS5 proxies, only for HTTP(S)
+            const proxyHost = proxyConfig.host.replace(/^\[|\]$/g, '');
+            if (details.challenger && details.challenger.host.replace(/^\[|\]$/g, '') !== proxyHost) {
                 console.warn('Detected authentication request for a different proxy host');
                 this.resetProxy();
                 this.setProxy(proxyConfig);
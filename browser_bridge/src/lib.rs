@@ -9,6 +9,7 @@ pub use core::{
     BrowserTimings,
     MyIP,
     PageParam,
+    PageStatus,
     random_user_agent,
 };
 pub use core::extension;
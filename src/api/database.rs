@@ -1,7 +1,8 @@
-use sqlx::{migrate::MigrateDatabase, sqlite::SqlitePoolOptions, Sqlite, SqlitePool};
+use sqlx::{migrate::MigrateDatabase, sqlite::SqlitePoolOptions, Executor, Sqlite, SqlitePool};
 
 use super::super::config as cfg;
-use super::super::models::api::{Task, Token};
+use super::super::models::api::{Order, ProxyRecord, Task, Token};
+use super::super::utils::timestamp_now;
 
 type Result<T> = core::result::Result<T, sqlx::Error>;
 pub type Pool = SqlitePool;
@@ -25,7 +26,65 @@ pub async fn init() -> Result<Pool> {
                 created_at INTEGER NOT NULL,
                 ttl INTEGER NOT NULL,
                 op_limit INTEGER NOT NULL,
-                tc_limit INTEGER NOT NULL
+                tc_limit INTEGER NOT NULL,
+                proxy_group TEXT,
+                priority INTEGER NOT NULL DEFAULT 0,
+                weight INTEGER NOT NULL DEFAULT 1,
+                express BOOLEAN NOT NULL DEFAULT 0
+            );"#,
+    )
+    .execute(&pool)
+    .await?;
+
+    // Базы, созданные до групп прокси
+    let (proxy_group_exists,): (bool,) = sqlx::query_as(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('tokens') WHERE name = 'proxy_group';",
+    )
+    .fetch_one(&pool)
+    .await?;
+    if !proxy_group_exists {
+        sqlx::query("ALTER TABLE tokens ADD COLUMN proxy_group TEXT;")
+            .execute(&pool)
+            .await?;
+    }
+    // Базы, созданные до приоритетов токенов
+    let (priority_exists,): (bool,) = sqlx::query_as(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('tokens') WHERE name = 'priority';",
+    )
+    .fetch_one(&pool)
+    .await?;
+    if !priority_exists {
+        sqlx::query("ALTER TABLE tokens ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;")
+            .execute(&pool)
+            .await?;
+        sqlx::query("ALTER TABLE tokens ADD COLUMN weight INTEGER NOT NULL DEFAULT 1;")
+            .execute(&pool)
+            .await?;
+    }
+    // Базы, созданные до срочных заказов
+    let (express_exists,): (bool,) = sqlx::query_as(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('tokens') WHERE name = 'express';",
+    )
+    .fetch_one(&pool)
+    .await?;
+    if !express_exists {
+        sqlx::query("ALTER TABLE tokens ADD COLUMN express BOOLEAN NOT NULL DEFAULT 0;")
+            .execute(&pool)
+            .await?;
+    }
+
+    sqlx::query(
+        r#"
+            CREATE TABLE IF NOT EXISTS proxies (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                url TEXT NOT NULL UNIQUE,
+                address TEXT NOT NULL,
+                group_name TEXT NOT NULL,
+                disabled BOOLEAN NOT NULL DEFAULT 0,
+                requests INTEGER NOT NULL DEFAULT 0,
+                failures INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL,
+                last_used_at INTEGER
             );"#,
     )
     .execute(&pool)
@@ -35,28 +94,55 @@ pub async fn init() -> Result<Pool> {
         r#"
             CREATE TABLE IF NOT EXISTS completed_tasks (
                 order_hash TEXT PRIMARY KEY,
-                data TEXT NOT NULL
+                data TEXT NOT NULL,
+                completed_at INTEGER NOT NULL DEFAULT 0
             );"#,
     )
     .execute(&pool)
     .await?;
 
-    sqlx::query(r#"DELETE FROM completed_tasks;"#)
-        .execute(&pool)
-        .await?;
+    // Базы, созданные до хранения результатов по времени
+    let (completed_at_exists,): (bool,) = sqlx::query_as(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('completed_tasks') WHERE name = 'completed_at';",
+    )
+    .fetch_one(&pool)
+    .await?;
+    if !completed_at_exists {
+        sqlx::query("ALTER TABLE completed_tasks ADD COLUMN completed_at INTEGER NOT NULL DEFAULT 0;")
+            .execute(&pool)
+            .await?;
+    }
+
+    // Задачи в очереди и в обработке: заказ сохраняется при постановке в очередь,
+    // состояние задачи - по мере обработки
+    sqlx::query(
+        r#"
+            CREATE TABLE IF NOT EXISTS active_tasks (
+                order_hash TEXT PRIMARY KEY,
+                token_id TEXT NOT NULL,
+                order_data TEXT NOT NULL,
+                data TEXT NOT NULL
+            );"#,
+    )
+    .execute(&pool)
+    .await?;
 
     Ok(pool)
 }
 
 pub async fn insert_token(pool: &Pool, token: &Token) -> Result<()> {
     sqlx::query(
-        "INSERT INTO tokens (id, created_at, ttl, op_limit, tc_limit) VALUES (?, ?, ?, ?, ?);",
+        "INSERT INTO tokens (id, created_at, ttl, op_limit, tc_limit, proxy_group, priority, weight, express) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?);",
     )
     .bind(token.id.as_str())
     .bind(token.created_at as i64)
     .bind(token.ttl as i64)
     .bind(token.op_limit as i64)
     .bind(token.tc_limit as i64)
+    .bind(token.proxy_group.as_deref())
+    .bind(token.priority)
+    .bind(token.weight)
+    .bind(token.express)
     .execute(pool)
     .await?;
 
@@ -64,10 +150,16 @@ pub async fn insert_token(pool: &Pool, token: &Token) -> Result<()> {
 }
 
 pub async fn update_token(pool: &Pool, token: &Token) -> Result<()> {
-    sqlx::query("UPDATE tokens SET ttl = ?, op_limit = ?, tc_limit = ? WHERE id = ?")
+    sqlx::query(
+        "UPDATE tokens SET ttl = ?, op_limit = ?, tc_limit = ?, proxy_group = ?, priority = ?, weight = ?, express = ? WHERE id = ?",
+    )
         .bind(token.ttl as i64)
         .bind(token.op_limit as i64)
         .bind(token.tc_limit as i64)
+        .bind(token.proxy_group.as_deref())
+        .bind(token.priority)
+        .bind(token.weight)
+        .bind(token.express)
         .bind(token.id.clone())
         .execute(pool)
         .await?;
@@ -104,17 +196,95 @@ pub async fn cutout_token(pool: &Pool, token_id: &str) -> Result<Option<Token>>
     Ok(token)
 }
 
-pub async fn insert_task(pool: &Pool, task: &Task) -> Result<()> {
+pub async fn insert_task<'e>(
+    executor: impl Executor<'e, Database = Sqlite>,
+    task: &Task,
+) -> Result<()> {
     let task_data = serde_json::to_string(task).unwrap();
-    sqlx::query("INSERT INTO completed_tasks (order_hash, data) VALUES (?, ?);")
-        .bind(task.order_hash.as_str())
+    sqlx::query(
+        "INSERT OR REPLACE INTO completed_tasks (order_hash, data, completed_at) VALUES (?, ?, ?);",
+    )
+    .bind(task.order_hash.as_str())
+    .bind(task_data)
+    .bind(timestamp_now() as i64)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Удаляет результаты задач, завершенных раньше `ttl` секунд назад. Возвращает количество
+/// удаленных задач.
+pub async fn purge_completed_tasks(pool: &Pool, ttl: u64) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM completed_tasks WHERE completed_at < ?;")
+        .bind(timestamp_now() as i64 - ttl as i64)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Сохраняет задачу, поставленную в очередь, вместе с заказом
+pub async fn insert_active_task(pool: &Pool, task: &Task) -> Result<()> {
+    let order_data = serde_json::to_string(&task.order).unwrap();
+    let task_data = serde_json::to_string(task).unwrap();
+    sqlx::query(
+        "INSERT OR REPLACE INTO active_tasks (order_hash, token_id, order_data, data) VALUES (?, ?, ?, ?);",
+    )
+    .bind(task.order_hash.as_str())
+    .bind(task.order.token_id.as_str())
+    .bind(order_data)
+    .bind(task_data)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Сохраняет состояние задачи в обработке (заказ не меняется)
+pub async fn update_active_task(pool: &Pool, task: &Task) -> Result<()> {
+    let task_data = serde_json::to_string(task).unwrap();
+    sqlx::query("UPDATE active_tasks SET data = ? WHERE order_hash = ?;")
         .bind(task_data)
+        .bind(task.order_hash.as_str())
         .execute(pool)
         .await?;
 
     Ok(())
 }
 
+/// Задачи в очереди и в обработке в порядке постановки в очередь
+pub async fn read_active_tasks(pool: &Pool) -> Result<Vec<Task>> {
+    let rows: Vec<(String, String, String, String)> = sqlx::query_as(
+        "SELECT order_hash, token_id, order_data, data FROM active_tasks ORDER BY rowid;",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(order_hash, token_id, order_data, task_data)| {
+            let mut task = serde_json::from_str::<Task>(&task_data).ok()?;
+            task.order = serde_json::from_str::<Order>(&order_data).ok()?;
+            task.order.token_id = token_id;
+            task.order_hash = order_hash;
+            Some(task)
+        })
+        .collect())
+}
+
+/// Переносит завершенную задачу из активных в выполненные
+pub async fn complete_task(pool: &Pool, task: &Task) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    insert_task(&mut *tx, task).await?;
+    sqlx::query("DELETE FROM active_tasks WHERE order_hash = ?;")
+        .bind(task.order_hash.as_str())
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await
+}
+
 // pub async fn task_exists(pool: &Pool, order_hash: &str) -> Result<bool> {
 //     let row: Option<(i64,)> = sqlx::query_as(
 //         "SELECT 1 FROM completed_tasks WHERE id = ?;"
@@ -136,6 +306,84 @@ pub async fn cutout_task(pool: &Pool, order_hash: &str) -> Result<Task> {
     Ok(serde_json::from_str(&task_data.0).unwrap())
 }
 
+/// Добавляет прокси в группу. Уже сохраненный прокси переносится в группу и включается.
+pub async fn upsert_proxy(pool: &Pool, url: &str, address: &str, group: &str) -> Result<ProxyRecord> {
+    let proxy: ProxyRecord = sqlx::query_as(
+        "INSERT INTO proxies (url, address, group_name, created_at) VALUES (?, ?, ?, ?)
+        ON CONFLICT(url) DO UPDATE SET group_name = excluded.group_name, disabled = 0
+        RETURNING *;",
+    )
+    .bind(url)
+    .bind(address)
+    .bind(group)
+    .bind(timestamp_now() as i64)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(proxy)
+}
+
+pub async fn read_proxies(pool: &Pool, group: Option<&str>) -> Result<Vec<ProxyRecord>> {
+    let proxies: Vec<ProxyRecord> =
+        sqlx::query_as("SELECT * FROM proxies WHERE ?1 IS NULL OR group_name = ?1 ORDER BY id;")
+            .bind(group)
+            .fetch_all(pool)
+            .await?;
+
+    Ok(proxies)
+}
+
+pub async fn read_proxy(pool: &Pool, proxy_id: i64) -> Result<Option<ProxyRecord>> {
+    let proxy: Option<ProxyRecord> = sqlx::query_as("SELECT * FROM proxies WHERE id = ?;")
+        .bind(proxy_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(proxy)
+}
+
+pub async fn disable_proxy(pool: &Pool, proxy_id: i64) -> Result<Option<ProxyRecord>> {
+    let proxy: Option<ProxyRecord> =
+        sqlx::query_as("UPDATE proxies SET disabled = 1 WHERE id = ? RETURNING *;")
+            .bind(proxy_id)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(proxy)
+}
+
+/// Включенные прокси группы для пула прокси заказа
+pub async fn read_group_proxy_pool(pool: &Pool, group: &str) -> Result<Vec<String>> {
+    let rows: Vec<(String,)> =
+        sqlx::query_as("SELECT url FROM proxies WHERE group_name = ? AND disabled = 0 ORDER BY id;")
+            .bind(group)
+            .fetch_all(pool)
+            .await?;
+
+    Ok(rows.into_iter().map(|(url,)| url).collect())
+}
+
+/// Учитывает запросы через прокси с адресом `address`
+pub async fn record_proxy_usage(
+    pool: &Pool,
+    address: &str,
+    requests: u64,
+    failures: u64,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE proxies SET requests = requests + ?, failures = failures + ?, last_used_at = ?
+        WHERE address = ?;",
+    )
+    .bind(requests as i64)
+    .bind(failures as i64)
+    .bind(timestamp_now() as i64)
+    .bind(address)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
 // pub async fn cutout_string_task(pool: &Pool, order_hash: &str) -> Result<String> {
 //     let task_data: (String,) = sqlx::query_as(
 //         "DELETE FROM completed_tasks WHERE order_hash = ? RETURNING data"
@@ -152,6 +400,7 @@ mod tests {
     use super::*;
     use crate::models::api as models;
     use crate::utils;
+    use std::collections::HashMap;
 
     #[tokio::test]
     async fn test_db_init() {
@@ -190,6 +439,25 @@ mod tests {
         assert_eq!(true, true);
     }
 
+    #[tokio::test]
+    async fn test_db_proxies() {
+        let pool = init().await.unwrap();
+        let group = utils::create_token_id();
+        let url = format!("http://user:pass@{}.example:8000", group.to_lowercase().replace('.', "-"));
+        let address = url.replace("user:pass@", "");
+        let proxy = upsert_proxy(&pool, &url, &address, &group).await.unwrap();
+        assert_eq!(read_group_proxy_pool(&pool, &group).await.unwrap(), vec![url.clone()]);
+
+        record_proxy_usage(&pool, &address, 10, 2).await.unwrap();
+        let disabled = disable_proxy(&pool, proxy.id).await.unwrap().unwrap();
+        assert_eq!((disabled.requests, disabled.failures, disabled.disabled), (10, 2, true));
+        assert!(read_group_proxy_pool(&pool, &group).await.unwrap().is_empty());
+
+        // Повторное добавление включает прокси
+        let proxy = upsert_proxy(&pool, &url, &address, &group).await.unwrap();
+        assert_eq!(read_proxies(&pool, Some(&group)).await.unwrap(), vec![proxy]);
+    }
+
     fn create_task() -> Task {
         let order = models::Order {
             token_id: utils::create_token_id(),
@@ -199,12 +467,19 @@ mod tests {
                 "oz/9999967890".into(),
                 "oz/7777767891".into(),
             ],
+            listing: None,
             proxy_pool: vec![
                 "EyPrWhn4uZ:wN1qqx1gPH@178.255.30.223:11223".into(),
                 "DF3fdv4uZ:w3ER56bi1gRp@185.255.30.168:11223".into(),
             ],
-            //proxy_map: HashMap::new(),
+            proxy_map: HashMap::new(),
+            proxy_rotation: None,
             cookies: Vec::new(),
+            diagnostics: false,
+            extended: false,
+            characteristics: false,
+            reviews: None,
+            express: false,
         };
         Task::from_order(order)
     }
@@ -218,6 +493,33 @@ mod tests {
         assert_eq!(insert_task_result.is_ok(), true);
     }
 
+    #[tokio::test]
+    async fn test_db_active_tasks() {
+        let pool = init().await.unwrap();
+        let mut task = create_task();
+        insert_active_task(&pool, &task).await.unwrap();
+        task.set_status(models::TaskStatus::Processing);
+        task.set_progress(1, 4);
+        update_active_task(&pool, &task).await.unwrap();
+
+        let restored = read_active_tasks(&pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|t| t.order_hash == task.order_hash)
+            .unwrap();
+        assert_eq!(restored.status, models::TaskStatus::Processing);
+        assert_eq!(restored.get_curr_step(), 1);
+        assert_eq!(restored.order.token_id, task.order.token_id);
+        assert_eq!(restored.order.products, task.order.products);
+
+        complete_task(&pool, &task).await.unwrap();
+        let active = read_active_tasks(&pool).await.unwrap();
+        assert!(active.iter().all(|t| t.order_hash != task.order_hash));
+        let completed = cutout_task(&pool, &task.order_hash).await.unwrap();
+        assert_eq!(completed.status, models::TaskStatus::Processing);
+    }
+
     #[tokio::test]
     async fn test_db_cutout_task() {
         let pool = init().await.unwrap();
@@ -231,4 +533,23 @@ mod tests {
         }
         assert_eq!(insert_task_result.is_ok(), true);
     }
+
+    #[tokio::test]
+    async fn test_db_purge_completed_tasks() {
+        let pool = init().await.unwrap();
+        let task = create_task();
+        insert_task(&pool, &task).await.unwrap();
+        purge_completed_tasks(&pool, 60).await.unwrap();
+        assert!(cutout_task(&pool, &task.order_hash).await.is_ok());
+
+        insert_task(&pool, &task).await.unwrap();
+        sqlx::query("UPDATE completed_tasks SET completed_at = ? WHERE order_hash = ?;")
+            .bind(timestamp_now() as i64 - 120)
+            .bind(task.order_hash.as_str())
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(purge_completed_tasks(&pool, 60).await.unwrap() >= 1);
+        assert!(cutout_task(&pool, &task.order_hash).await.is_err());
+    }
 }
@@ -113,6 +113,10 @@ const DEFAULT_API_DESCRIPTION: &'static str = r#"
 - Пул прокси-серверов (`proxyPool`)
 - Пользовательские cookies (`cookies`)
 
+Дополнительно можно включить режим диагностики (`"diagnostics": true`), расширенные данные о товаре (`"extended": true`), характеристики товара (`"characteristics": true`) и отзывы о товаре (`"reviews": {...}`).
+
+Срочный заказ (`"express": true`) начинается раньше несрочных заказов в очереди. Срочные заказы доступны токенам с разрешением `express` (`/token-info`), для остальных токенов заказ отклоняется с ошибкой `InvalidOrderParameter`.
+
 ```json
 {"products": [], "proxyPool": [], "cookies": []}
 ```
@@ -129,13 +133,52 @@ const DEFAULT_API_DESCRIPTION: &'static str = r#"
 
 2. Полный URL товара с маркетплейса
 
+#### Заказ выдачи (поиск и категории)
+
+Вместо списка товаров заказ может содержать поле `listing` — поисковый запрос или ссылку на категорию маркетплейса. Парсер проходит страницы выдачи по порядку и возвращает найденные товары с основными полями `ProductData`.
+
+```json
+{"listing": {"market": "wb", "query": "футболка оверсайз", "pages": 3, "limit": 200}, "proxyPool": []}
+```
+
+```json
+{"listing": {"url": "https://www.ozon.ru/category/nozhnitsy-15696/", "pages": 2, "scrape": true}}
+```
+
+- **market** (`string`) — символ маркетплейса для поискового запроса.
+- **query** (`string`) — поисковый запрос.
+- **url** (`string`) — ссылка на категорию или поиск маркетплейса. Маркетплейс определяется по ссылке.
+- **pages** (`int`, по умолчанию `1`, не более `50`) — количество страниц выдачи.
+- **limit** (`int`, по умолчанию лимит токена) — максимальное количество товаров, не больше лимита токена на количество товаров в заказе.
+- **scrape** (`bool`) — после прохода выдачи запросить данные найденных товаров, как в обычном заказе.
+
+Поиск поддерживается для всех маркетплейсов, ссылки на категории — кроме Wildberries. Выдача обрабатывается до пустой страницы или до `limit` товаров. Прогресс задачи считается по страницам выдачи, а при `"scrape": true` после выдачи — по найденным товарам. Ключи результата имеют тот же формат `символ/идентификатор`, поэтому найденные товары можно отправить обычным заказом. Классификация страниц выдачи (`outcomes`) и диагностика записываются по ссылкам страниц.
+
+#### Заказ витрины продавца
+
+Поле `seller` в `listing` задает продавца в формате `символ/идентификатор_продавца` — идентификатор из ссылки на магазин. Кроме товаров витрины, задача возвращает профиль продавца в поле `seller`.
+
+```json
+{"listing": {"seller": "oz/ofismag-172339", "pages": 2}}
+```
+
+```json
+{
+    ...
+    "seller": {"id": "ofismag-172339", "url": "https://www.ozon.ru/seller/ofismag-172339/", "name": "ОфисМаг", "legalName": "ООО «ОФИСМАГ»", "inn": "7721234567", "ogrn": "1027700123456", "address": "...", "rating": 4.9, "reviews": 58214},
+    "result": {"data": {"oz/1736756863": {...}, ...}}
+}
+```
+
+Поля профиля заполняются, если маркетплейс их отдает. Классификация и диагностика страницы профиля записываются по ключу `seller`. Остальные поля `listing` (`pages`, `limit`, `scrape`) работают так же, как для выдачи.
+
 ---
 
 ### 3. Отправка заказа и получение результатов
 
 Процесс парсинга состоит из следующих шагов:
 1. Отправка заказа методом [/order](#/order/order)
-2. При успешной обработке возвращается `order_hash` для отслеживания статуса выполнения
+2. При успешной обработке возвращается `order_hash` для отслеживания статуса выполнения. Он зависит от токена, списка товаров или выдачи и параметров результата (`extended`, `characteristics`, `reviews`, `diagnostics`, `express`)
 3. Мониторинг выполнения через REST API или [WebSocket](https://ru.wikipedia.org/wiki/WebSocket)
 
 ### REST API мониторинг
@@ -171,6 +214,56 @@ const DEFAULT_API_DESCRIPTION: &'static str = r#"
 
 Эти ситуации неизбежны при работе с динамическими ресурсами, поэтому иногда не удается получить данные о товаре. Ошибки часто возникают из-за блокировки со стороны ресурса. Для обхода блокировок рекомендуется использовать прокси-серверы.
 
+Если страница товара распознана как страница блокировки, капчи или отсутствующего товара, причина записывается в поле `outcomes` задачи:
+
+```json
+{
+    ...
+    "outcomes": { "oz/1736756863": "blocked", "ym/1732949807-100352880819-5997015": "captcha", "wb/145700662": "notFound" }
+}
+```
+
+После страницы блокировки или капчи следующий запрос выполняется через другой прокси из `proxyPool` (кроме стратегии `sticky`, см. `proxyRotation`). Серия товаров без данных (кроме `notFound`) прерывает выполнение задачи со статусом `interrupted`.
+
+После временной ошибки (ошибка сети, HTTP 429 и 5xx) или капчи запрос товара повторяется с растущей задержкой через другой прокси (параметры `[req_session.retry]` в конфигурации). Количество попыток для товаров, запрошенных больше одного раза, записывается в поле `attempts` задачи:
+
+```json
+{
+    ...
+    "attempts": { "oz/1736756863": 2, "ym/1732949807-100352880819-5997015": 3 }
+}
+```
+
+Товары маркетплейса запрашиваются одновременно в пределах лимита `[req_session.parallelism]` конфигурации (по умолчанию по одному): несколько запросов без браузера или несколько вкладок браузера. Браузерные маркетплейсы с прокси обрабатываются по одному товару, так как прокси меняется для всего браузера. Товары добавляются в результат и прогресс задачи в порядке заказа.
+
+Товары маркетплейсов с пакетными запросами (`wb`) запрашиваются пакетами до `[req_session.batch_size]` товаров одним запросом. Если ответ на пакетный запрос не удалось разделить по товарам, товары пакета запрашиваются по одному. Временные ошибки пакетного запроса повторяются по `[req_session.retry]`, после последней попытки товары пакета завершаются с ошибкой без запросов по одному. В диагностике товаров из пакета поле `fetch.batch` - количество товаров в пакете.
+
+#### Диагностика
+
+Если в заказе указано `"diagnostics": true`, задача дополнительно содержит поле `diagnostics` с результатом обработки каждого товара:
+
+```json
+{
+    ...
+    "diagnostics": {
+        "oz/1736756863": {
+            "status": "ok",
+            "fetch": { "status": 200, "bytes": 48213, "elapsedMs": 1840 },
+            "fields": { "price": "pointer '/widgetStates/webPrice-*/price': 'webPrice-*' not found" }
+        },
+        "wb/145700662": {
+            "status": "ok",
+            "fetch": { "bytes": 0, "elapsedMs": 30001, "error": "Request sending error" }
+        }
+    }
+}
+```
+
+- **status** — классификация страницы: `ok`, `blocked`, `captcha` или `notFound`.
+- **fetch** — результат запроса страницы: HTTP статус (только для запросов без браузера), размер контента, время выполнения и ошибка запроса.
+- **error** — причина, по которой контент не удалось разобрать целиком (например, страница блокировки без ожидаемых элементов).
+- **fields** — поля, которые не удалось извлечь, и причины.
+
 ### Данные о товаре
 
 `ProductData` представляет собой структуру данных, используемую для описания товара. Она содержит информацию о товаре, включая его идентификатор, название, цену, продавца и другие атрибуты.
@@ -210,6 +303,61 @@ const DEFAULT_API_DESCRIPTION: &'static str = r#"
 - **brand** (`null | string`)
   Бренд товара.
 
+### Расширенные поля
+
+Возвращаются только для заказов с `"extended": true` и только если маркетплейс предоставляет эти данные.
+
+- **oldPrice** (`null | int64`)
+  Старая (зачеркнутая) цена.
+
+- **discount** (`null | int64`)
+  Скидка в процентах.
+
+- **available** (`null | bool`)
+  Наличие товара.
+
+- **stock** (`null | int64`)
+  Остаток товара.
+
+- **delivery** (`null | string`)
+  Дата или срок доставки.
+
+- **categories** (`null | string[]`)
+  Категории товара от общей к частной.
+
+- **images** (`null | string[]`)
+  Все изображения товара.
+
+- **variants** (`null | object[]`)
+  Варианты товара (размеры, цвета): `sku`, `name`, `price`, `stock`, `available`.
+
+### Характеристики
+
+Возвращаются только для заказов с `"characteristics": true`. Для Ozon и Wildberries характеристики запрашиваются отдельным запросом, поэтому обработка товара занимает больше времени.
+
+- **characteristics** (`null | object`)
+  Характеристики товара в виде `{"название": "значение"}`, например `{"Цвет": "черный", "Состав": "хлопок 100%"}`.
+
+Если характеристики не удалось получить, основные данные о товаре все равно возвращаются, а причина ошибки попадает в диагностику (поле `fields.characteristics`).
+
+### Отзывы
+
+Возвращаются только для заказов с полем `reviews`. Отзывы запрашиваются по страницам после данных о товаре и попадают в результат вместе с ним. Поддерживаются Ozon, Wildberries и Яндекс.Маркет.
+
+```json
+{"products": ["wb/248939630"], "reviews": {"limit": 50, "since": "2024-01-01"}}
+```
+
+- **limit** (`int`, по умолчанию `100`, не более `1000`) — максимальное количество отзывов на товар.
+- **since** (`string`) — дата в формате `YYYY-MM-DD`, более ранние отзывы не возвращаются.
+
+Страницы отзывов запрашиваются до `limit` отзывов, до пустой страницы или до страницы, на которой все отзывы раньше `since`.
+
+- **reviewItems** (`null | object[]`)
+  Отзывы о товаре: `id`, `author`, `stars` (оценка от 1 до 5), `text`, `date` (`YYYY-MM-DD`), `photos` (есть ли в отзыве фотографии).
+
+Если отзывы не удалось получить, основные данные о товаре все равно возвращаются, а причина ошибки попадает в диагностику (поле `fields.reviews`).
+
 ---
 
 ## Особенности работы
@@ -222,11 +370,49 @@ API предоставляет два основных механизма защ
 
 1. **Прокси-пул (ProxyPool)**:
    - Позволяет распределять запросы через разные IP-адреса.
-   - Поддерживает формат подключения: `USERNAME:PASSWORD@HOST:PORT`.
+   - Поддерживает формат подключения: `[СХЕМА://][USERNAME:PASSWORD@]HOST:PORT`.
+   - Схемы: `http` (по умолчанию), `https`, `socks5` и `socks5h`. Учетные данные необязательны, специальные символы в них кодируются как в ссылке (`p%40ss`).
+   - HOST — IPv4, доменное имя или IPv6 в квадратных скобках: `socks5://[2001:db8::1]:1080`.
+   - Браузер не поддерживает авторизацию на SOCKS5-прокси, поэтому для Ozon, Яндекс.Маркета и МегаМаркета SOCKS5-прокси должны работать без учетных данных. Заказ с такими прокси для этих маркетплейсов (в `proxyPool`, `proxyMap` или в группе прокси токена) отклоняется с ошибкой `InvalidOrderParameter`.
    - Возможность указания нескольких прокси-серверов.
    - Геолокация прокси может влиять на доступность товаров и их цены, которые зависят от региона.
 
-Отсутствие ProxyPool может привести к блокировке запросов из-за превышения лимита обращений с одного IP-адреса (сервера парсера).
+Проверить прокси перед отправкой заказа можно методом `/proxy-check`.
+
+Отсутствие ProxyPool может привести к блокировке запросов из-за превышения лимита обращений с одного IP-адреса (сервера парсера). Если токену назначена группа прокси сервера (`proxyGroup` в `/token-info`), заказы без `proxyPool` используют прокси этой группы.
+
+   Если прокси работают не на всех маркетплейсах, для отдельных маркетплейсов можно указать свои пулы в `proxyMap` по символу маркетплейса. Маркетплейсы без пула в `proxyMap` используют `proxyPool`.
+
+```json
+{"products": ["wb/145700662", "oz/1736756863"], "proxyPool": ["user:pass@192.0.2.10:8000"], "proxyMap": {"oz": ["user:pass@198.51.100.7:8000", "user:pass@198.51.100.8:8000"]}}
+```
+
+   Во время задачи учитывается состояние каждого прокси. Ошибка запроса или страница блокировки — неудачный запрос. После нескольких неудачных запросов подряд прокси уходит на карантин и не используется, после нескольких карантинов исключается до конца задачи (параметры `[req_session.proxy_health]` в конфигурации). Если все прокси пула исключены, запросы через этот пул завершаются ошибкой. Статистика прокси записывается в поле `proxies` задачи без учетных данных:
+
+```json
+{
+    ...
+    "proxies": {
+        "http://192.0.2.10:8000": { "requests": 120, "successes": 117, "failures": 3, "avgLatencyMs": 1840, "state": "active" },
+        "http://198.51.100.7:8000": { "requests": 9, "successes": 0, "failures": 9, "avgLatencyMs": 30001, "state": "evicted" }
+    }
+}
+```
+
+   Состояние прокси: `active`, `quarantined` (временно не используется) или `evicted` (исключен).
+
+   Стратегия смены прокси задается в заказе полем `proxyRotation`, по умолчанию — параметром `proxy_rotation` секции `[req_session]` конфигурации. Стратегия применяется к запросам через браузер и без него, отдельно для каждого маркетплейса. N — параметр `set_proxy_interval`.
+
+   - `roundRobin` — следующий прокси пула каждые N запросов и после неудачного запроса (по умолчанию).
+   - `random` — случайный прокси каждые N запросов и после неудачного запроса.
+   - `leastRecentlyUsed` — давно не использованный прокси каждые N запросов и после неудачного запроса.
+   - `sticky` — один прокси на маркетплейс, пока он не уйдет на карантин.
+   - `onFailure` — следующий прокси пула только после неудачного запроса.
+   - `weighted` — случайный прокси с вероятностью по доле успешных запросов каждые N запросов и после неудачного запроса.
+
+```json
+{"products": ["wb/145700662", "oz/1736756863"], "proxyPool": ["192.0.2.10:8000", "192.0.2.11:8000"], "proxyRotation": "weighted"}
+```
 
 2. **Пользовательские Cookies**:
    - Сохраняют авторизационные данные и настройки пользователя.
@@ -269,6 +455,7 @@ API использует унифицированную систему кодо
 | **InvalidOrderFormat** | Не удалось десериализовать тело запроса в объект заказа | **203** | 400 |
 | **EmptyRequestBody** | Тело запроса пусто. Ожидается определенная структура | **204** | 400 |
 | **EmptyOrder** | Отправленный заказ пуст | **205** | 400 |
+| **InvalidProxyList** | Недопустимый список прокси | **206** | 400 |
 | **QueueOverflow** | Очередь обработчика заполнена. Достигнуто максимальное количество задач | **300** | 409 |
 | **ProductLimitExceeded** | Заказ превышает максимальный лимит продуктов | **301** | 409 |
 | **ConcurrencyLimitExceeded** | Токен превысил лимит одновременной обработки | **302** | 409 |
@@ -277,11 +464,13 @@ API использует унифицированную систему кодо
 | **AccessRestricted** | Доступ к методу ограничен | **305** | 409 |
 | **TokenDoesNotExist** | Токен не существует | **400** | 404 |
 | **TaskNotFound** | Задача с указанным order_hash не существует | **401** | 404 |
+| **ProxyDoesNotExist** | Прокси с указанным id не существует в хранилище сервера | **402** | 404 |
 | **PathNotFound** | Запрошенный путь не найден | **404** | 404 |
-| **TaskSendFailure** | Не удалось отправить задачу обработчику | **500** | 500 |
 | **ReqwestSessionError** | Ошибка сессии запроса | **501** | 500 |
 | **DatabaseError** | Сбой транзакции базы данных | **502** | 500 |
 | **SerializationError** | Не удалось сериализовать объект | **503** | 500 |
+| **InvalidExtractionRules** | Ошибка в файле правил извлечения данных | **504** | 500 |
+| **TaskHandlerFailure** | Обработчик задачи аварийно завершился во время обработки,</br>возвращается в результате задачи со статусом `error` | **505** | 500 |
 </br>
 
 ---
@@ -317,7 +506,11 @@ API использует унифицированную систему кодо
         markets,
         order,
         valid_order,
+        proxy_check,
         task,
+        cancel_task,
+        pause_task,
+        resume_task,
         task_ws
     ),
 )]
@@ -390,6 +583,7 @@ fn ping() {}
 - **ttl** - Время жизни токена в секундах
 - **orderProductsLimit** - Лимит токена на количество товаров в заказе
 - **taskCountLimit** - Лимит токена на количество параллельных обработок заказа
+- **proxyGroup** - Группа прокси сервера, которая используется в заказах без `proxyPool` (если назначена)
 
 ```python
 import requests
@@ -554,6 +748,29 @@ fn config() {}
 - Текущем количестве задач в очереди
 - Лимите открытых WebSocket соединений
 - Текущем количестве открытых WebSocket соединений
+- Лимитах частоты запросов к маркетплейсам (`rateLimits`)
+- Состоянии обработчиков задач (`handlers`)
+
+Лимиты задаются в `[api.rate_limit]` конфигурации и общие для всех обработчиков:
+token bucket с `rps` запросами в секунду и накоплением до `burst` запросов.
+При `per_proxy = true` у каждого прокси маркетплейса свой лимит (ключ `<symbol>@<proxy>`).
+В состоянии показываются маркетплейсы, к которым уже были запросы:
+```json
+"rateLimits": {
+    "oz": { "rps": 2.0, "burst": 4, "tokens": 0.0, "waitMs": 1500, "requests": 318 },
+    "wb": { "rps": 5.0, "burst": 10, "tokens": 7.4, "waitMs": 0, "requests": 42 }
+}
+```
+`waitMs` - ожидание очереди следующего запроса.
+
+Состояние обработчиков задач (`handlers`): обработчик, аварийно завершившийся во время обработки задачи, перезапускается, а его задача завершается со статусом `error` и ошибкой `TaskHandlerFailure`:
+```json
+"handlers": [
+    { "alive": true, "restarts": 0, "orderHash": "2f1c0e9a4b7d8e6f5a3c1b0d9e8f7a6b5c4d3e2f" },
+    { "alive": true, "restarts": 1, "lastError": "index out of bounds: the len is 0 but the index is 0" }
+]
+```
+`restarts` - количество перезапусков, `orderHash` - задача в обработке, `lastError` - причина последнего аварийного завершения.
 "#,
     responses(
         (status = 200, description = "Состояние API", body = ApiState, content_type = "application/json")
@@ -624,10 +841,11 @@ fn markets() {}
 * Информацию по доступным маркетплейсам и их символы можно получить используя метод /markets
 
 **Параметры прокси:**
-- Формат записи: USERNAME:PASSWORD@HOST:PORT
+- Формат записи: [СХЕМА://][USERNAME:PASSWORD@]HOST:PORT, схемы http, https, socks5, socks5h
 - Можно указать несколько прокси-серверов
+- proxyMap — отдельные пулы прокси по символам маркетплейсов
 
-Параметры proxyPool и cookies опциональны. Отсутствие proxyPool может привести к блокировке запросов из-за превышения лимита обращений с одного IP адреса (сервера парсера).
+Параметры proxyPool, proxyMap и cookies опциональны. Отсутствие proxyPool может привести к блокировке запросов из-за превышения лимита обращений с одного IP адреса (сервера парсера).
 
 **Особенности:**
 - При успешной обработке возвращается order_hash
@@ -794,6 +1012,75 @@ print(validated_order)
 #[allow(dead_code)]
 fn valid_order() {}
 
+#[utoipa::path(
+    post,
+    path = "/proxy-check",
+    tags = ["order"],
+    context_path = &*ROOT_API_PATH,
+    description = r#"
+### POST /proxy-check
+Метод проверки прокси перед отправкой заказа. Каждый прокси проверяется запросом к сервису внешнего IP (`check_url` секции `[req_session.proxy_health]` конфигурации) без браузера (`reqwest`) и через браузер (`browser`). Не больше 20 прокси за запрос.
+
+**Заголовок запроса:**
+- Authorization: Bearer <YOUR-TOKEN>
+
+**Тело запроса:** JSON массив прокси в формате `proxyPool`.
+
+**Результат проверки** по прокси без учетных данных:
+- **ok** - Сервис внешнего IP доступен через прокси
+- **status** - HTTP статус ответа (только без браузера)
+- **latencyMs** - Время запроса
+- **ip** - Внешний IP-адрес прокси
+- **country** - Код страны внешнего IP-адреса
+- **auth** - Авторизация на прокси прошла успешно (только для прокси с учетными данными, если это удалось определить)
+- **error** - Ошибка запроса
+
+Проверки через браузер выполняются сервером по одной и занимают не больше 60 секунд вместе с ожиданием очереди. Если очередь занята, браузерная проверка возвращает ошибку `browser check is busy`, прокси, до которых не дошла очередь за это время, - ошибку `browser check timed out`. Проверка без браузера выполняется всегда.
+
+```python
+import requests
+
+headers = {
+    "Authorization": "Bearer your-token-here"
+}
+
+response = requests.post("https://rustscraper.ru/api/proxy-check", headers=headers, json=["user:pass@192.0.2.10:8000"])
+print(response.json())
+```
+"#,
+    request_body(
+        content = Vec<String>, content_type = "application/json", description = "Список прокси",
+        example = json!(["user:pass@192.0.2.10:8000", "socks5://192.0.2.11:1080"])
+    ),
+    security(
+        ("Token" = [])
+    ),
+    responses(
+        (
+            status = 200, description = "Результаты проверки прокси", content_type = "application/json",
+            example = json!({
+                "http://192.0.2.10:8000": {
+                    "reqwest": {"ok": true, "status": 200, "latencyMs": 412, "ip": "192.0.2.10", "country": "RU", "auth": true},
+                    "browser": {"ok": true, "latencyMs": 1630, "ip": "192.0.2.10", "country": "RU", "auth": true}
+                },
+                "socks5://192.0.2.11:1080": {
+                    "reqwest": {"ok": false, "latencyMs": 700, "error": "error sending request for url (http://ip-api.com/json): operation timed out"},
+                    "browser": {"ok": false, "latencyMs": 30001, "error": "BrowserError: Timeout"}
+                }
+            })
+        ),
+        (status = 400, description = r#"
+### Ошибка ApiError
+
+Значения ошибок смотреть в таблице ApiError
+"#,
+        body = ApiError, content_type = "application/json",
+        example = json!({"error":"InvalidProxyList","code":206,"message":"Invalid proxy list: proxy port number: '99999'."}))
+    )
+)]
+#[allow(dead_code)]
+fn proxy_check() {}
+
 #[utoipa::path(
     get,
     path = "/task/{order_hash}",
@@ -830,6 +1117,10 @@ while task["status"] in ("waiting", "processing"):
 ```
 
 Для отслеживания выполнения задачи рекомендуется использовать подключение через WebSocket.
+
+`queueNum` - позиция задачи в очереди, начиная с 1: сколько задач начнется раньше нее с учетом политики очереди. У задачи в обработке и приостановленной задачи `queueNum` равен 0. Обработчики начинают сначала срочные заказы (`"express": true`), затем заказы токенов с большим приоритетом. Между токенами с одинаковым приоритетом обработка делится пропорционально весу токена с учетом объема заказов (количества товаров или страниц выдачи), поэтому большие заказы одного токена не задерживают заказы других токенов. Заказы одного токена начинаются в порядке оформления.
+
+Задачи сохраняются в базе и переживают перезапуск сервера: задачи из очереди ставятся в очередь заново, задачи, которые обрабатывались во время перезапуска, завершаются со статусом `interrupted` и частичным результатом. Завершенная задача возвращается один раз - после получения она удаляется. Результат, который не был получен, хранится `completed_task_ttl` секунд конфигурации (по умолчанию 7 дней).
 "#,
     params(
         (
@@ -859,6 +1150,153 @@ while task["status"] in ("waiting", "processing"):
 #[allow(dead_code)]
 fn task() {}
 
+#[utoipa::path(
+    delete,
+    path = "/task/{order_hash}",
+    tags = ["order"],
+    context_path = &*ROOT_API_PATH,
+    description = r#"
+### DELETE /task/{order_hash}
+Метод отмены задачи в очереди или в обработке. Отменить задачу может только токен, которым оформлен заказ.
+
+**Параметры пути:**
+- order_hash: Уникальный идентификатор заказа
+
+**Заголовок запроса:**
+- Authorization: Bearer <YOUR-TOKEN>
+
+Задача из очереди и приостановленная задача отменяются сразу, выполняющаяся задача останавливается после текущего товара. Отмененная задача завершается со статусом `cancelled` и полученными до отмены товарами, результат доступен методом `/task/{order_hash}`.
+
+Ответ - состояние задачи на момент отмены:
+- **200 OK** - задача отменена, статус `cancelled`
+- **202 Accepted** - отмена принята, задача в обработке остановится после текущего товара, статус сменится на `cancelled`. Повторная отмена возвращает тот же ответ, пока задача не остановится.
+
+```python
+import requests
+
+headers = {
+    "Authorization": "Bearer your-token-here"
+}
+
+order_hash = "your-order-hash"
+response = requests.delete(f"https://rustscraper.ru/api/task/{order_hash}", headers=headers)
+print(response.json())
+```
+"#,
+    params(
+        (
+			"order_hash" = String, Path,
+			description = r#"order_hash заказа"#
+		),
+    ),
+    security(
+        ("Token" = [])
+    ),
+    responses(
+        (
+            status = 200, description = "Состояние задачи", body = Task, content_type = "application/json",
+            example = json!({"queueNum":0,"status":"cancelled","createdAt":1736857399})
+        ),
+        (
+            status = 202, description = "Отмена принята, задача остановится после текущего товара", body = Task, content_type = "application/json",
+            example = json!({"queueNum":0,"status":"processing","progress":[120,5000],"createdAt":1736857399})
+        ),
+        (status = 404, description = r#"
+### Задача не найдена
+
+Задачи нет в очереди и в обработке или она оформлена другим токеном
+"#,
+		body = ApiError, content_type = "application/json",
+        example = json!({"error":"TaskNotFound","code":401,"message":"A task with the specified order_hash does not exist."}))
+    )
+)]
+#[allow(dead_code)]
+fn cancel_task() {}
+
+#[utoipa::path(
+    post,
+    path = "/task/{order_hash}/pause",
+    tags = ["order"],
+    context_path = &*ROOT_API_PATH,
+    description = r#"
+### POST /task/{order_hash}/pause
+Метод приостановки задачи, например на время замены прокси или ограничений маркетплейса. Приостановить задачу может только токен, которым оформлен заказ.
+
+**Заголовок запроса:**
+- Authorization: Bearer <YOUR-TOKEN>
+
+Выполняющаяся задача останавливается после текущего товара со статусом `paused`, браузер задачи закрывается. Задача из очереди приостанавливается сразу и убирается из очереди. Заказ выдачи приостанавливается после текущей страницы выдачи и продолжается со следующей страницы с уже найденными товарами, заказ выдачи с `scrape` после сбора выдачи - после текущего товара. Приостановленная задача сохраняется при перезапуске сервера и не учитывается в лимите очереди и лимите токена `tc_limit` до возобновления.
+
+Ответ - состояние задачи на момент запроса.
+"#,
+    params(
+        (
+			"order_hash" = String, Path,
+			description = r#"order_hash заказа"#
+		),
+    ),
+    security(
+        ("Token" = [])
+    ),
+    responses(
+        (
+            status = 200, description = "Состояние задачи", body = Task, content_type = "application/json",
+            example = json!({"queueNum":0,"status":"processing","progress":[120,5000],"createdAt":1736857399})
+        ),
+        (status = 404, description = r#"
+### Задача не найдена
+
+Задачи нет в очереди и в обработке или она оформлена другим токеном
+"#,
+		body = ApiError, content_type = "application/json",
+        example = json!({"error":"TaskNotFound","code":401,"message":"A task with the specified order_hash does not exist."}))
+    )
+)]
+#[allow(dead_code)]
+fn pause_task() {}
+
+#[utoipa::path(
+    post,
+    path = "/task/{order_hash}/resume",
+    tags = ["order"],
+    context_path = &*ROOT_API_PATH,
+    description = r#"
+### POST /task/{order_hash}/resume
+Метод возобновления приостановленной задачи. Задача ставится в очередь со статусом `waiting` и продолжается с текущего шага `progress` с уже полученным результатом.
+
+**Заголовок запроса:**
+- Authorization: Bearer <YOUR-TOKEN>
+
+Запрос к задаче, которая еще не приостановилась, отменяет приостановку.
+
+При возобновлении проверяются лимит очереди (`QueueOverflow`) и лимит токена `tc_limit` (`ConcurrencyLimitExceeded`): приостановленная задача в них не учитывается.
+"#,
+    params(
+        (
+			"order_hash" = String, Path,
+			description = r#"order_hash заказа"#
+		),
+    ),
+    security(
+        ("Token" = [])
+    ),
+    responses(
+        (
+            status = 200, description = "Состояние задачи", body = Task, content_type = "application/json",
+            example = json!({"queueNum":1,"status":"waiting","progress":[120,5000],"createdAt":1736857399})
+        ),
+        (status = 404, description = r#"
+### Задача не найдена
+
+Задачи нет в очереди и в обработке или она оформлена другим токеном
+"#,
+		body = ApiError, content_type = "application/json",
+        example = json!({"error":"TaskNotFound","code":401,"message":"A task with the specified order_hash does not exist."}))
+    )
+)]
+#[allow(dead_code)]
+fn resume_task() {}
+
 #[utoipa::path(
     get,
     path = "/task-ws/{order_hash}",
@@ -6,7 +6,10 @@ use axum::{
 use thiserror::Error;
 use utoipa::ToSchema;
 
-use super::super::{models::validation::ValidationError, scraper::error::ReqSessionError};
+use super::super::{
+    models::validation::ValidationError,
+    scraper::error::{ReqSessionError, RulesError},
+};
 
 #[derive(Error, Debug, ToSchema)]
 pub enum ApiError {
@@ -46,6 +49,9 @@ pub enum ApiError {
     #[error("{{ \"error\": \"EmptyOrder\", \"code\": 205, \"message\": \"The submitted order is empty.\" }}")]
     EmptyOrder,
 
+    #[error("{{ \"error\": \"InvalidProxyList\", \"code\": 206, \"message\": \"Invalid proxy list: {0}.\" }}")]
+    InvalidProxyList(String),
+
     #[error("{{ \"error\": \"QueueOverflow\", \"code\": 300, \"message\": \"Handler queue is full. Maximum tasks allowed: '{0}'.\" }}")]
     QueueOverflow(u64),
 
@@ -70,12 +76,12 @@ pub enum ApiError {
     #[error("{{ \"error\": \"TaskNotFound\", \"code\": 401, \"message\": \"A task with the specified order_hash does not exist.\" }}")]
     TaskNotFound,
 
+    #[error("{{ \"error\": \"ProxyDoesNotExist\", \"code\": 402, \"message\": \"Proxy does not exist.\" }}")]
+    ProxyDoesNotExist,
+
     #[error("{{ \"error\": \"PathNotFound\", \"code\": 404, \"message\": \"The requested path was not found.\" }}")]
     PathNotFound,
 
-    #[error("{{ \"error\": \"TaskSendFailure\", \"code\": 500, \"message\": \"Failed to send the task to the handler.\" }}")]
-    TaskSendFailure,
-
     #[error("{{ \"error\": \"ReqwestSessionError\", \"code\": 501, \"message\": \"{0}.\" }}")]
     ReqwestSessionError(String),
 
@@ -84,6 +90,12 @@ pub enum ApiError {
 
     #[error("{{ \"error\": \"SerializationError\", \"code\": 503, \"message\": \"Failed to serialize object.\" }}")]
     SerializationError,
+
+    #[error("{{ \"error\": \"InvalidExtractionRules\", \"code\": 504, \"message\": \"{0}.\" }}")]
+    InvalidExtractionRules(String),
+
+    #[error("{{ \"error\": \"TaskHandlerFailure\", \"code\": 505, \"message\": \"The task handler crashed: {0}.\" }}")]
+    TaskHandlerFailure(String),
 }
 
 impl ApiError {
@@ -95,11 +107,13 @@ impl ApiError {
             | Self::InvalidOrderParameter(_)
             | Self::InvalidOrderFormat
             | Self::EmptyRequestBody(_)
-            | Self::EmptyOrder => StatusCode::BAD_REQUEST,
+            | Self::EmptyOrder
+            | Self::InvalidProxyList(_) => StatusCode::BAD_REQUEST,
 
-            Self::TaskNotFound | Self::TokenDoesNotExist | Self::PathNotFound => {
-                StatusCode::NOT_FOUND
-            }
+            Self::TaskNotFound
+            | Self::TokenDoesNotExist
+            | Self::ProxyDoesNotExist
+            | Self::PathNotFound => StatusCode::NOT_FOUND,
 
             Self::QueueOverflow(_)
             | Self::DuplicateTask(_)
@@ -115,9 +129,10 @@ impl ApiError {
 
             Self::UnknownError
             | Self::DatabaseError
-            | Self::TaskSendFailure
             | Self::ReqwestSessionError(_)
-            | Self::SerializationError => StatusCode::INTERNAL_SERVER_ERROR,
+            | Self::SerializationError
+            | Self::InvalidExtractionRules(_)
+            | Self::TaskHandlerFailure(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
@@ -152,6 +167,13 @@ impl From<ReqSessionError> for ApiError {
     }
 }
 
+impl From<RulesError> for ApiError {
+    fn from(value: RulesError) -> Self {
+        let message = serde_json::to_string(&value.to_string()).unwrap_or_default();
+        ApiError::InvalidExtractionRules(message.trim_matches('"').into())
+    }
+}
+
 impl From<ValidationError> for ApiError {
     fn from(value: ValidationError) -> Self {
         match value {
@@ -162,6 +184,14 @@ impl From<ValidationError> for ApiError {
             ValidationError::Product(e) => {
                 ApiError::InvalidOrderParameter(format!("order product {}", e))
             }
+
+            ValidationError::Listing(e) => {
+                ApiError::InvalidOrderParameter(format!("order listing {}", e))
+            }
+
+            ValidationError::Reviews(e) => {
+                ApiError::InvalidOrderParameter(format!("order reviews {}", e))
+            }
         }
     }
 }
@@ -1,6 +1,7 @@
 pub mod database;
 pub mod routers;
 pub mod states;
+pub mod scheduler;
 pub mod logger;
 pub mod error;
 pub mod doc;
@@ -21,17 +21,23 @@ use std::{
 use tokio::time::{sleep, timeout};
 use tower_http::services::ServeFile;
 use utils::{
-    extract_and_handle_order_from_body, extract_token_from_headers, get_query_param,
-    log_middleware, new_token_from_query, verify_master_token, verify_token,
+    extract_and_handle_order_from_body, extract_proxy_list_from_body, extract_token_from_headers,
+    get_query_param, log_middleware, new_token_from_query, proxy_group_validation,
+    verify_master_token, verify_token,
 };
 
 use super::{
     super::{
         config as cfg,
         models::{
-            api::{ApiState, Token},
+            api::{ApiState, TaskStatus, Token},
             scraper::MARKET_MAP,
-            validation::Validation,
+            validation::{browser_proxy_validation, Validation, ValidationError},
+        },
+        scraper::{
+            extractor::rules,
+            limiter,
+            proxy::{check_proxies, check_proxy, proxy_label},
         },
         utils::list_dir,
     },
@@ -43,6 +49,9 @@ use super::{
 static TASK_WS_SENDING_INTERVAL: LazyLock<u64> =
     LazyLock::new(|| cfg::get().api.task_ws_sending_interval);
 
+/// Максимальное количество прокси в одной проверке
+const PROXY_CHECK_LIMIT: usize = 20;
+
 pub fn api(app_state: Arc<AppState>) -> Router {
     Router::new()
         .route("/state", routing::get(state))
@@ -52,11 +61,22 @@ pub fn api(app_state: Arc<AppState>) -> Router {
         .route("/token-info", routing::get(token_info))
         .route("/token-info/{token_id}", routing::get(token_info_))
         .route("/test-token", routing::get(test_token))
+        .route("/add-proxies/", routing::post(add_proxies))
+        .route("/proxies", routing::get(proxies))
+        .route("/disable-proxy/{proxy_id}", routing::post(disable_proxy))
+        .route("/test-proxy/{proxy_id}", routing::post(test_proxy))
         .route("/order", routing::post(order))
-        .route("/task/{order_hash}", routing::get(task).post(task))
+        .route(
+            "/task/{order_hash}",
+            routing::get(task).post(task).delete(cancel_task),
+        )
+        .route("/task/{order_hash}/pause", routing::post(pause_task))
+        .route("/task/{order_hash}/resume", routing::post(resume_task))
         .route("/task-ws/{order_hash}", routing::any(task_ws))
         .route("/valid-order", routing::post(valid_order).get(valid_order))
+        .route("/proxy-check", routing::post(proxy_check))
         .with_state(app_state)
+        .route("/reload-rules", routing::post(reload_rules))
         .route("/admin", routing::get(admin))
         .route("/config", routing::get(config))
         .route("/markets", routing::get(markets))
@@ -207,11 +227,90 @@ async fn test_token(
     Ok((StatusCode::CREATED, Json(test_token)).into_response())
 }
 
+#[debug_handler]
+async fn add_proxies(
+    headers: HeaderMap,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HashMap<String, String>>,
+    body: Bytes,
+) -> Result<Response, ApiError> {
+    verify_master_token(&headers)?;
+
+    let group = proxy_group_validation(get_query_param(&query, "group")?)?;
+    let proxy_list = extract_proxy_list_from_body(&body)?;
+    let mut added = Vec::with_capacity(proxy_list.len());
+    for url in proxy_list {
+        added.push(db::upsert_proxy(&state.db_pool, &url, &proxy_label(&url), &group).await?);
+    }
+
+    Ok((StatusCode::CREATED, Json(added)).into_response())
+}
+
+#[debug_handler]
+async fn proxies(
+    headers: HeaderMap,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<Response, ApiError> {
+    verify_master_token(&headers)?;
+
+    let proxies = db::read_proxies(&state.db_pool, query.get("group").map(String::as_str)).await?;
+
+    Ok((StatusCode::OK, Json(proxies)).into_response())
+}
+
+#[debug_handler]
+async fn disable_proxy(
+    headers: HeaderMap,
+    State(state): State<Arc<AppState>>,
+    Path(proxy_id): Path<String>,
+) -> Result<Response, ApiError> {
+    verify_master_token(&headers)?;
+
+    let proxy_id = proxy_id
+        .parse::<i64>()
+        .map_err(|_| ApiError::ProxyDoesNotExist)?;
+    let proxy = db::disable_proxy(&state.db_pool, proxy_id)
+        .await?
+        .ok_or(ApiError::ProxyDoesNotExist)?;
+
+    Ok((StatusCode::OK, Json(proxy)).into_response())
+}
+
+#[debug_handler]
+async fn test_proxy(
+    headers: HeaderMap,
+    State(state): State<Arc<AppState>>,
+    Path(proxy_id): Path<String>,
+) -> Result<Response, ApiError> {
+    verify_master_token(&headers)?;
+
+    let proxy_id = proxy_id
+        .parse::<i64>()
+        .map_err(|_| ApiError::ProxyDoesNotExist)?;
+    let proxy = db::read_proxy(&state.db_pool, proxy_id)
+        .await?
+        .ok_or(ApiError::ProxyDoesNotExist)?;
+    let check = check_proxy(&proxy.url).await;
+    db::record_proxy_usage(&state.db_pool, &proxy.address, 1, !check.ok as u64).await?;
+
+    Ok((StatusCode::OK, Json(check)).into_response())
+}
+
 #[debug_handler]
 async fn config() -> Response {
     (StatusCode::OK, Json(cfg::get())).into_response()
 }
 
+#[debug_handler]
+async fn reload_rules(headers: HeaderMap) -> Result<Response, ApiError> {
+    verify_master_token(&headers)?;
+
+    let rules = rules::reload()?;
+
+    Ok((StatusCode::OK, Json(&rules.source)).into_response())
+}
+
 #[debug_handler]
 async fn state(State(state): State<Arc<AppState>>) -> Response {
     let api_state = ApiState {
@@ -220,6 +319,8 @@ async fn state(State(state): State<Arc<AppState>>) -> Response {
         curr_task_queue: state.get_task_count().await,
         open_ws_limit: state.open_ws_limit,
         curr_open_ws: *state.open_ws_counter.lock().await,
+        rate_limits: limiter::state(),
+        handlers: state.handlers_state(),
     };
 
     (StatusCode::OK, Json(api_state)).into_response()
@@ -242,12 +343,27 @@ async fn order(
     if order.products.len() > token.op_limit as usize {
         return Err(ApiError::ProductLimitExceeded(token.op_limit));
     }
+    if let Some(listing) = order.listing.as_mut() {
+        if listing.limit > token.op_limit {
+            return Err(ApiError::ProductLimitExceeded(token.op_limit));
+        }
+        if listing.limit == 0 {
+            listing.limit = token.op_limit;
+        }
+    }
+    if order.express && !token.express {
+        return Err(ApiError::InvalidOrderParameter("express".into()));
+    }
     if state.task_count_by_token_id(token_id).await >= token.tc_limit as usize {
         return Err(ApiError::ConcurrencyLimitExceeded(token.tc_limit));
     }
     order.validation()?;
+    if let (true, Some(group)) = (order.proxy_pool.is_empty(), &token.proxy_group) {
+        order.proxy_pool = db::read_group_proxy_pool(&state.db_pool, group).await?;
+        browser_proxy_validation(&order).map_err(ValidationError::Proxy)?;
+    }
     order.token_id = token_id.into();
-    let order_hash = state.insert_order(order).await?;
+    let order_hash = state.insert_order(order, &token).await?;
 
     Ok((StatusCode::OK, order_hash).into_response())
 }
@@ -269,6 +385,25 @@ async fn valid_order(
     Ok((StatusCode::OK, Json(order)).into_response())
 }
 
+#[debug_handler]
+async fn proxy_check(
+    headers: HeaderMap,
+    State(state): State<Arc<AppState>>,
+    body: Bytes,
+) -> Result<Response, ApiError> {
+    let token_id = extract_token_from_headers(&headers)?;
+    let _ = verify_token(token_id, &state.db_pool).await?;
+    let proxy_list = extract_proxy_list_from_body(&body)?;
+    if proxy_list.len() > PROXY_CHECK_LIMIT {
+        return Err(ApiError::InvalidProxyList(format!(
+            "more than {PROXY_CHECK_LIMIT} proxies"
+        )));
+    }
+    let reports = check_proxies(&proxy_list).await;
+
+    Ok((StatusCode::OK, Json(reports)).into_response())
+}
+
 #[debug_handler]
 async fn task(
     headers: HeaderMap,
@@ -282,6 +417,50 @@ async fn task(
     Ok((StatusCode::OK, Json(task)).into_response())
 }
 
+#[debug_handler]
+async fn cancel_task(
+    headers: HeaderMap,
+    State(state): State<Arc<AppState>>,
+    Path(order_hash): Path<String>,
+) -> Result<Response, ApiError> {
+    let token_id = extract_token_from_headers(&headers)?;
+    let token = verify_token(token_id, &state.db_pool).await?;
+    let task = state.cancel_task(&order_hash, &token.id).await?;
+    // Выполняющаяся задача отменяется после текущего товара
+    let status = match task.status {
+        TaskStatus::Cancelled => StatusCode::OK,
+        _ => StatusCode::ACCEPTED,
+    };
+
+    Ok((status, Json(task)).into_response())
+}
+
+#[debug_handler]
+async fn pause_task(
+    headers: HeaderMap,
+    State(state): State<Arc<AppState>>,
+    Path(order_hash): Path<String>,
+) -> Result<Response, ApiError> {
+    let token_id = extract_token_from_headers(&headers)?;
+    let token = verify_token(token_id, &state.db_pool).await?;
+    let task = state.pause_task(&order_hash, &token.id).await?;
+
+    Ok((StatusCode::OK, Json(task)).into_response())
+}
+
+#[debug_handler]
+async fn resume_task(
+    headers: HeaderMap,
+    State(state): State<Arc<AppState>>,
+    Path(order_hash): Path<String>,
+) -> Result<Response, ApiError> {
+    let token_id = extract_token_from_headers(&headers)?;
+    let token = verify_token(token_id, &state.db_pool).await?;
+    let task = state.resume_task(&order_hash, &token.id).await?;
+
+    Ok((StatusCode::OK, Json(task)).into_response())
+}
+
 #[debug_handler]
 async fn task_ws(
     ws: WebSocketUpgrade,
@@ -360,7 +539,7 @@ const ADMIN_DOC: &'static str = r#"
 <h2>1. POST /create-token/</h2>
 <p>Создает новый токен.</p>
 <ul>
-    <li><strong>Запрос:</strong> Заголовок `Authorization: Bearer <master_token>`, параметры запроса: `ttl`, `op_limit`, `tc_limit`.</li>
+    <li><strong>Запрос:</strong> Заголовок `Authorization: Bearer <master_token>`, параметры запроса: `ttl`, `op_limit`, `tc_limit`, необязательный `proxy_group` — группа прокси сервера для заказов без `proxyPool`, необязательные `priority` (по умолчанию 0) — приоритет заказов токена в очереди, `weight` (по умолчанию 1) — доля обработки относительно токенов с тем же приоритетом и `express` (по умолчанию `false`) — разрешение срочных заказов.</li>
     <li><strong>Ответ:</strong> 201 Created, токен в формате JSON.</li>
     <li><strong>Ошибки:</strong> 401 Unauthorized, 400 Bad Request.</li>
 </ul>
@@ -376,11 +555,51 @@ const ADMIN_DOC: &'static str = r#"
 <h2>3. POST /update-token/</h2>
 <p>Обновляет параметры токена.</p>
 <ul>
-    <li><strong>Запрос:</strong> Заголовок `Authorization: Bearer <master_token>`, параметры запроса: `id`, `ttl`, `op_limit`, `tc_limit`.</li>
+    <li><strong>Запрос:</strong> Заголовок `Authorization: Bearer <master_token>`, параметры запроса: `id`, `ttl`, `op_limit`, `tc_limit`, необязательный `proxy_group` (без него группа снимается), необязательные `priority`, `weight` и `express` (без них устанавливаются значения по умолчанию).</li>
     <li><strong>Ответ:</strong> 201 Created, обновлённый токен в формате JSON.</li>
     <li><strong>Ошибки:</strong> 401 Unauthorized, 400 Bad Request.</li>
 </ul>
 
+<h2>4. POST /reload-rules</h2>
+<p>Перечитывает файл правил извлечения данных (`rules_file_path`) без перезапуска сервиса. Файл должен содержать правила для всех поддерживаемых маркетплейсов. Без файла загружаются встроенные правила, файл не создается.</p>
+<ul>
+    <li><strong>Запрос:</strong> Заголовок `Authorization: Bearer <master_token>`.</li>
+    <li><strong>Ответ:</strong> 200 OK, загруженные правила в формате JSON. При ошибке остаются действующие правила.</li>
+    <li><strong>Ошибки:</strong> 401 Unauthorized, 500 InvalidExtractionRules.</li>
+</ul>
+
+<h2>5. POST /add-proxies/</h2>
+<p>Добавляет прокси в хранилище сервера. Заказы токенов с группой `proxy_group` без `proxyPool` используют включенные прокси группы. Уже добавленный прокси переносится в группу и включается.</p>
+<ul>
+    <li><strong>Запрос:</strong> Заголовок `Authorization: Bearer <master_token>`, параметр запроса `group`, тело — JSON массив прокси в формате `proxyPool`.</li>
+    <li><strong>Ответ:</strong> 201 Created, добавленные прокси в формате JSON.</li>
+    <li><strong>Ошибки:</strong> 401 Unauthorized, 400 Bad Request (InvalidProxyList).</li>
+</ul>
+
+<h2>6. GET /proxies</h2>
+<p>Список прокси хранилища со статистикой: количество запросов (`requests`), неудачных запросов (`failures`) и время последнего использования (`lastUsedAt`). Статистика учитывается по завершении задач и при проверке прокси.</p>
+<ul>
+    <li><strong>Запрос:</strong> Заголовок `Authorization: Bearer <master_token>`, необязательный параметр запроса `group`.</li>
+    <li><strong>Ответ:</strong> 200 OK, прокси в формате JSON.</li>
+    <li><strong>Ошибки:</strong> 401 Unauthorized.</li>
+</ul>
+
+<h2>7. POST /disable-proxy/{proxy_id}</h2>
+<p>Отключает прокси: он больше не попадает в заказы.</p>
+<ul>
+    <li><strong>Запрос:</strong> Заголовок `Authorization: Bearer <master_token>`, параметр пути `proxy_id`.</li>
+    <li><strong>Ответ:</strong> 200 OK, прокси в формате JSON.</li>
+    <li><strong>Ошибки:</strong> 401 Unauthorized, 404 ProxyDoesNotExist.</li>
+</ul>
+
+<h2>8. POST /test-proxy/{proxy_id}</h2>
+<p>Проверяет прокси запросом к `check_url` секции `[req_session.proxy_health]` конфигурации.</p>
+<ul>
+    <li><strong>Запрос:</strong> Заголовок `Authorization: Bearer <master_token>`, параметр пути `proxy_id`.</li>
+    <li><strong>Ответ:</strong> 200 OK, `{"ok": true, "status": 200, "latencyMs": 412}` или `{"ok": false, "latencyMs": 700, "error": "..."}`.</li>
+    <li><strong>Ошибки:</strong> 401 Unauthorized, 404 ProxyDoesNotExist.</li>
+</ul>
+
 <h2>Авторизация</h2>
 <p>Все методы требуют заголовка: `Authorization: Bearer <master_token>`.</p>
 
@@ -394,6 +613,22 @@ Authorization: Bearer your_master_token
 
 POST /update-token/?id=12345&ttl=7200&op_limit=2000&tc_limit=2
 Authorization: Bearer your_master_token
+
+POST /reload-rules
+Authorization: Bearer your_master_token
+
+POST /add-proxies/?group=main
+Authorization: Bearer your_master_token
+["user:pass@192.0.2.10:8000", "socks5://192.0.2.11:1080"]
+
+GET /proxies?group=main
+Authorization: Bearer your_master_token
+
+POST /disable-proxy/3
+Authorization: Bearer your_master_token
+
+POST /test-proxy/3
+Authorization: Bearer your_master_token
 </pre>
 
 </body>
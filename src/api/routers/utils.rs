@@ -4,7 +4,11 @@ use std::collections::HashMap;
 
 use crate::{
     api::{app::MASTER_TOKEN, database as db, error::ApiError, logger},
-    models::api::{Order, Token},
+    models::{
+        api::{Order, Token},
+        validation::proxy_str_validation,
+    },
+    utils::remove_duplicates,
 };
 
 #[inline]
@@ -60,7 +64,7 @@ pub async fn verify_token(token_id: &str, db_pool: &db::Pool) -> Result<Token, A
 
 #[inline]
 pub fn new_token_from_query(query: &HashMap<String, String>) -> Result<Token, ApiError> {
-    let new_token = Token::new(
+    let mut new_token = Token::new(
         get_query_param(query, "ttl")?
             .parse::<u64>()
             .map_err(|_| ApiError::InvalidUrlQueryParameter("ttl".into()))?,
@@ -71,10 +75,61 @@ pub fn new_token_from_query(query: &HashMap<String, String>) -> Result<Token, Ap
             .parse::<u64>()
             .map_err(|_| ApiError::InvalidUrlQueryParameter("tc_limit".into()))?,
     );
+    new_token.proxy_group = query
+        .get("proxy_group")
+        .map(|group| proxy_group_validation(group))
+        .transpose()?;
+    if let Some(priority) = query.get("priority") {
+        new_token.priority = priority
+            .parse::<u32>()
+            .map_err(|_| ApiError::InvalidUrlQueryParameter("priority".into()))?;
+    }
+    if let Some(weight) = query.get("weight") {
+        new_token.weight = weight
+            .parse::<u32>()
+            .ok()
+            .filter(|w| *w > 0)
+            .ok_or(ApiError::InvalidUrlQueryParameter("weight".into()))?;
+    }
+    if let Some(express) = query.get("express") {
+        new_token.express = express
+            .parse::<bool>()
+            .map_err(|_| ApiError::InvalidUrlQueryParameter("express".into()))?;
+    }
 
     Ok(new_token)
 }
 
+/// Название группы прокси: латинские буквы, цифры, `-` и `_`
+#[inline]
+pub fn proxy_group_validation(group: &str) -> Result<String, ApiError> {
+    let valid = (1..=64).contains(&group.len())
+        && group
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(ApiError::InvalidUrlQueryParameter("proxy_group".into()));
+    }
+
+    Ok(group.into())
+}
+
+#[inline]
+pub fn extract_proxy_list_from_body(body: &Bytes) -> Result<Vec<String>, ApiError> {
+    if body.is_empty() {
+        return Err(ApiError::EmptyRequestBody("Vec<String>".into()));
+    }
+    let mut proxy_list = serde_json::from_slice::<Vec<String>>(body)
+        .map_err(|_| ApiError::InvalidProxyList("expected a JSON array of strings".into()))?;
+    for proxy in proxy_list.iter_mut() {
+        *proxy = proxy_str_validation(proxy)
+            .map_err(|e| ApiError::InvalidProxyList(format!("proxy {}", e)))?;
+    }
+    remove_duplicates(&mut proxy_list);
+
+    Ok(proxy_list)
+}
+
 #[inline]
 pub fn get_query_param<'a>(
     query: &'a HashMap<String, String>,
@@ -92,7 +147,7 @@ pub fn extract_and_handle_order_from_body(body: &Bytes) -> Result<Order, ApiErro
     }
     let mut order =
         serde_json::from_slice::<Order>(&body).map_err(|_| ApiError::InvalidOrderFormat)?;
-    if order.products.is_empty() {
+    if order.products.is_empty() && order.listing.is_none() {
         return Err(ApiError::EmptyOrder);
     }
     order.remove_duplicates();
@@ -0,0 +1,196 @@
+use super::super::models::api::{Task, Token};
+use std::collections::HashMap;
+
+type OrderHash = String;
+
+/// Задача в очереди планировщика
+#[derive(Clone, Debug)]
+pub struct QueueEntry {
+    pub order_hash: OrderHash,
+    pub token_id: String,
+    pub priority: u32,
+    pub weight: u32,
+    pub express: bool,
+    /// Объем работы задачи: количество товаров или страниц выдачи
+    pub cost: u64,
+    seq: u64,
+}
+
+impl QueueEntry {
+    pub fn new(task: &Task, token: Option<&Token>) -> Self {
+        let order = &task.order;
+        let cost = match &order.listing {
+            Some(listing) if listing.scrape => listing.pages + listing.limit,
+            Some(listing) => listing.pages,
+            None => order.products.len() as u64,
+        };
+        Self {
+            order_hash: task.order_hash.clone(),
+            token_id: order.token_id.clone(),
+            priority: token.map_or(0, |t| t.priority),
+            weight: token.map_or(1, |t| t.weight).max(1),
+            // Разрешение срочных заказов могло быть снято с токена после оформления заказа
+            express: order.express && token.is_some_and(|t| t.express),
+            cost: cost.max(1),
+            seq: 0,
+        }
+    }
+}
+
+/// Очередь задач обработчиков.
+///
+/// Срочные заказы начинаются раньше остальных, затем заказы токенов с большим приоритетом.
+/// Между токенами с одинаковым приоритетом обработка делится пропорционально весу токена:
+/// следующей начинается задача токена с наименьшим объемом начатой работы, деленным на вес.
+/// Задачи одного токена начинаются в порядке постановки в очередь.
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    entries: Vec<QueueEntry>,
+    /// Виртуальное время токенов: объем начатой работы, деленный на вес
+    passes: HashMap<String, f64>,
+    /// Виртуальное время последней начатой задачи, с него начинают токены без задач
+    vtime: f64,
+    seq: u64,
+}
+
+impl Scheduler {
+    pub fn push(&mut self, mut entry: QueueEntry) {
+        self.seq += 1;
+        entry.seq = self.seq;
+        self.entries.push(entry);
+    }
+
+    pub fn remove(&mut self, order_hash: &str) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|e| e.order_hash != order_hash);
+
+        len != self.entries.len()
+    }
+
+    /// Следующая задача для обработчика
+    pub fn pop(&mut self) -> Option<QueueEntry> {
+        let index = next_index(&self.entries, &self.passes, self.vtime)?;
+        let entry = self.entries.remove(index);
+        start(&mut self.passes, &mut self.vtime, &entry);
+
+        Some(entry)
+    }
+
+    /// Позиции задач в очереди, начиная с 1, в порядке, в котором их начнут обработчики
+    pub fn positions(&self) -> HashMap<OrderHash, u64> {
+        let mut entries = self.entries.clone();
+        let mut passes = self.passes.clone();
+        let mut vtime = self.vtime;
+        let mut positions = HashMap::with_capacity(entries.len());
+        while let Some(index) = next_index(&entries, &passes, vtime) {
+            let entry = entries.remove(index);
+            start(&mut passes, &mut vtime, &entry);
+            positions.insert(entry.order_hash, positions.len() as u64 + 1);
+        }
+
+        positions
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[inline]
+fn pass(passes: &HashMap<String, f64>, vtime: f64, token_id: &str) -> f64 {
+    passes.get(token_id).copied().unwrap_or(0.0).max(vtime)
+}
+
+fn next_index(entries: &[QueueEntry], passes: &HashMap<String, f64>, vtime: f64) -> Option<usize> {
+    entries
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            b.express
+                .cmp(&a.express)
+                .then(b.priority.cmp(&a.priority))
+                .then(
+                    pass(passes, vtime, &a.token_id).total_cmp(&pass(passes, vtime, &b.token_id)),
+                )
+                .then(a.seq.cmp(&b.seq))
+        })
+        .map(|(i, _)| i)
+}
+
+fn start(passes: &mut HashMap<String, f64>, vtime: &mut f64, entry: &QueueEntry) {
+    let pass = pass(passes, *vtime, &entry.token_id);
+    *vtime = pass;
+    passes.insert(
+        entry.token_id.clone(),
+        pass + entry.cost as f64 / entry.weight as f64,
+    );
+    // Токены, отставшие от виртуального времени, равны токенам без задач
+    passes.retain(|_, p| *p > *vtime);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::api::Order;
+
+    fn entry(order_hash: &str, token_id: &str, priority: u32, weight: u32, cost: u64) -> QueueEntry {
+        QueueEntry {
+            order_hash: order_hash.into(),
+            token_id: token_id.into(),
+            priority,
+            weight,
+            express: false,
+            cost,
+            seq: 0,
+        }
+    }
+
+    fn order(scheduler: &mut Scheduler) -> Vec<String> {
+        std::iter::from_fn(|| scheduler.pop().map(|e| e.order_hash)).collect()
+    }
+
+    #[test]
+    fn test_scheduler() {
+        // Большие заказы одного токена не блокируют заказы другого токена
+        let mut scheduler = Scheduler::default();
+        scheduler.push(entry("a1", "a", 0, 1, 100));
+        scheduler.push(entry("a2", "a", 0, 1, 100));
+        scheduler.push(entry("a3", "a", 0, 1, 100));
+        scheduler.push(entry("b1", "b", 0, 1, 10));
+        scheduler.push(entry("b2", "b", 0, 1, 10));
+        let positions = scheduler.positions();
+        assert_eq!(order(&mut scheduler), ["a1", "b1", "b2", "a2", "a3"]);
+        assert_eq!(positions["a1"], 1);
+        assert_eq!(positions["b2"], 3);
+        assert_eq!(positions["a3"], 5);
+
+        // Вес токена, приоритет и срочные заказы
+        let mut scheduler = Scheduler::default();
+        scheduler.push(entry("a1", "a", 0, 2, 10));
+        scheduler.push(entry("a2", "a", 0, 2, 10));
+        scheduler.push(entry("b1", "b", 0, 1, 10));
+        scheduler.push(entry("b2", "b", 0, 1, 10));
+        scheduler.push(entry("p1", "p", 1, 1, 10));
+        scheduler.push(QueueEntry {
+            express: true,
+            ..entry("e1", "b", 0, 1, 10)
+        });
+        assert!(scheduler.remove("b2"));
+        assert_eq!(scheduler.len(), 5);
+        assert_eq!(order(&mut scheduler), ["e1", "p1", "a1", "a2", "b1"]);
+
+        // Срочный заказ только у токена с разрешением
+        let task = Task::from_order(Order {
+            products: vec!["wb/1".into(), "wb/2".into()],
+            express: true,
+            ..Default::default()
+        });
+        let mut token = Token::new(60, 10, 1);
+        assert!(!QueueEntry::new(&task, Some(&token)).express);
+        token.express = true;
+        let entry = QueueEntry::new(&task, Some(&token));
+        assert!(entry.express);
+        assert_eq!(entry.cost, 2);
+    }
+}
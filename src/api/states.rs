@@ -1,25 +1,40 @@
 use super::{
-    super::models::api::{Order, Task},
-    super::scraper::stream::task_stream,
+    super::config as cfg,
+    super::models::api::{HandlerState, Order, Task, TaskStatus, Token},
+    super::scraper::stream::{task_stream, TaskControl},
     database as db,
     error::ApiError,
+    logger,
+    scheduler::{QueueEntry, Scheduler},
 };
 use sqlx::SqlitePool;
 use std::{
     collections::{HashMap, HashSet},
-    sync::Arc,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, LazyLock},
+    time::{Duration, Instant},
 };
 use tokio::{
-    sync::{
-        mpsc::{self, Receiver, Sender},
-        Mutex, RwLock,
-    },
+    sync::{Mutex, Notify, RwLock},
     task::JoinHandle,
 };
 use tokio_stream::StreamExt;
 
 type OrderHash = String;
 
+/// Обработка задачи обработчиком
+type ProcessTask = fn(TaskHandler, OrderHash) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Интервал удаления результатов задач, которые не были получены
+const COMPLETED_TASKS_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Пауза перед перезапуском аварийно завершившегося обработчика
+const HANDLER_RESTART_DELAY: Duration = Duration::from_secs(1);
+
+static TASK_PERSIST_INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_millis(cfg::get().api.task_persist_interval));
+
 pub struct Cache {
     pub blocked_addrs: HashSet<String>,
 }
@@ -32,86 +47,338 @@ impl Cache {
     }
 }
 
+/// Общая очередь задач и обработчики, которые берут из нее задачи по политике [`Scheduler`]
+#[derive(Clone)]
 struct TaskHandler {
+    pub db_pool: Arc<db::Pool>,
     pub task_heap: Arc<RwLock<HashMap<OrderHash, Task>>>,
+    pub controls: Arc<RwLock<HashMap<OrderHash, TaskControl>>>,
+    pub scheduler: Arc<Mutex<Scheduler>>,
+    pub notify: Arc<Notify>,
+    pub handlers: Arc<std::sync::Mutex<Vec<HandlerState>>>,
     pub queue_limit: u64,
-    pub sender: Sender<OrderHash>,
-    //pub join_handle: JoinHandle<()>
+    process: ProcessTask,
 }
 
 impl TaskHandler {
-    pub async fn run(db_pool: Arc<SqlitePool>, queue_limit: usize) -> Self {
-        let task_heap = Arc::new(RwLock::new(HashMap::with_capacity(queue_limit)));
-        let (sender, receiver) = mpsc::channel::<OrderHash>(queue_limit);
-        let _join_handle = Self::spawn_handler(db_pool, receiver, task_heap.clone()).await;
+    pub async fn run(db_pool: Arc<SqlitePool>, handlers_count: usize, queue_limit: usize) -> Self {
+        Self::run_with(db_pool, handlers_count, queue_limit, |handler, order_hash| {
+            Box::pin(async move { handler.process_task(order_hash).await })
+        })
+    }
 
-        Self {
-            task_heap: task_heap,
+    fn run_with(
+        db_pool: Arc<SqlitePool>,
+        handlers_count: usize,
+        queue_limit: usize,
+        process: ProcessTask,
+    ) -> Self {
+        let handler = Self {
+            db_pool,
+            task_heap: Arc::new(RwLock::new(HashMap::with_capacity(queue_limit))),
+            controls: Arc::new(RwLock::new(HashMap::with_capacity(queue_limit))),
+            scheduler: Arc::new(Mutex::new(Scheduler::default())),
+            notify: Arc::new(Notify::new()),
+            handlers: Arc::new(std::sync::Mutex::new(vec![
+                HandlerState::default();
+                handlers_count
+            ])),
             queue_limit: queue_limit as u64,
-            sender,
-            //join_handle
+            process,
+        };
+        for index in 0..handlers_count {
+            let _join_handle = handler.clone().supervise(index);
         }
+
+        handler
     }
 
-    async fn spawn_handler(
-        db_pool: Arc<db::Pool>,
-        mut receiver: Receiver<OrderHash>,
-        task_heap: Arc<RwLock<HashMap<OrderHash, Task>>>,
-    ) -> JoinHandle<()> {
+    /// Надзор за обработчиком: обработчик, аварийно завершившийся во время обработки,
+    /// перезапускается, а его задача завершается со статусом `error`
+    fn supervise(self, index: usize) -> JoinHandle<()> {
         tokio::spawn(async move {
-            while let Some(order_hash) = receiver.recv().await {
-                let task = task_heap.read().await.get(&order_hash).unwrap().clone();
-                let mut stream = task_stream(task.clone()).await;
-
-                while let Some(task) = stream.next().await {
-                    if !task.is_done_by_status() {
-                        task_heap.write().await.insert(order_hash.clone(), task);
-                    } else {
-                        if task_heap.write().await.remove(&order_hash).is_some() {
-                            task_heap
-                                .write()
-                                .await
-                                .values_mut()
-                                .for_each(|t| t.queue_num -= 1);
-                        }
-
-                        let _ = db::insert_task(&db_pool, &task).await;
-                    }
+            loop {
+                self.handler_state(index, |state| state.alive = true);
+                let result = self.clone().spawn_handler(index).await;
+                let order_hash = self.handler_state(index, |state| {
+                    state.alive = false;
+                    state.order_hash.take()
+                });
+                let reason = match result {
+                    Ok(()) => "handler stopped".to_string(),
+                    Err(e) if e.is_panic() => panic_message(e.into_panic()),
+                    Err(_) => break,
+                };
+                logger::write(
+                    log::Level::Error,
+                    "TASK_HANDLER_RESTART",
+                    format!("handler: {index}, task: {order_hash:?}, reason: {reason}"),
+                )
+                .await;
+                if let Some(order_hash) = order_hash {
+                    self.fail_task(&order_hash, &reason).await;
                 }
+                self.handler_state(index, |state| {
+                    state.restarts += 1;
+                    state.last_error = Some(reason);
+                });
+                tokio::time::sleep(HANDLER_RESTART_DELAY).await;
+            }
+        })
+    }
+
+    fn spawn_handler(self, index: usize) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let order_hash = self.next_task().await;
+                self.handler_state(index, |state| state.order_hash = Some(order_hash.clone()));
+                (self.process)(self.clone(), order_hash).await;
+                self.handler_state(index, |state| state.order_hash = None);
             }
         })
     }
 
-    pub async fn registering_task(&self, mut task: Task) -> Result<OrderHash, ApiError> {
-        let task_count = self.task_heap.read().await.len() as u64;
+    fn handler_state<R>(&self, index: usize, f: impl FnOnce(&mut HandlerState) -> R) -> R {
+        let mut handlers = self.handlers.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut handlers[index])
+    }
+
+    /// Завершение задачи аварийно завершившегося обработчика со статусом `error`
+    async fn fail_task(&self, order_hash: &OrderHash, reason: &str) {
+        let Some(mut task) = self.task_heap.write().await.remove(order_hash) else {
+            return;
+        };
+        self.controls.write().await.remove(order_hash);
+        task.set_status(TaskStatus::Error);
+        task.set_result_error(ApiError::TaskHandlerFailure(reason.replace('"', "'")));
+
+        record_proxy_usage(&self.db_pool, &task).await;
+        let _ = db::complete_task(&self.db_pool, &task).await;
+    }
+
+    /// Ожидание следующей задачи из очереди
+    async fn next_task(&self) -> OrderHash {
+        loop {
+            {
+                let mut scheduler = self.scheduler.lock().await;
+                if let Some(entry) = scheduler.pop() {
+                    self.update_queue_nums(&scheduler).await;
+                    // Задачи остались в очереди: свободный обработчик не должен ждать
+                    if scheduler.len() > 0 {
+                        self.notify.notify_one();
+                    }
+                    return entry.order_hash;
+                }
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    async fn process_task(&self, order_hash: OrderHash) {
+        // Задача отменена, пока была в очереди
+        let Some(task) = self.task_heap.read().await.get(&order_hash).cloned() else {
+            return;
+        };
+        let control = self
+            .controls
+            .read()
+            .await
+            .get(&order_hash)
+            .cloned()
+            .unwrap_or_default();
+        if control.is_paused() {
+            self.store_paused(task, &control).await;
+            return;
+        }
+        let mut stream = task_stream(task.clone(), control.clone()).await;
+        let mut persisted = (task.status.clone(), Instant::now());
+
+        while let Some(task) = stream.next().await {
+            if task.status == TaskStatus::Paused {
+                record_proxy_usage(&self.db_pool, &task).await;
+                self.store_paused(task, &control).await;
+            } else if !task.is_done_by_status() {
+                // Прогресс сохраняется не чаще `task_persist_interval`, смена статуса - сразу
+                if task.status != persisted.0 || persisted.1.elapsed() >= *TASK_PERSIST_INTERVAL {
+                    let _ = db::update_active_task(&self.db_pool, &task).await;
+                    persisted = (task.status.clone(), Instant::now());
+                }
+                self.task_heap.write().await.insert(order_hash.clone(), task);
+            } else {
+                self.controls.write().await.remove(&order_hash);
+                self.task_heap.write().await.remove(&order_hash);
+
+                record_proxy_usage(&self.db_pool, &task).await;
+                let _ = db::complete_task(&self.db_pool, &task).await;
+            }
+        }
+    }
+
+    /// Сохранение приостановленной задачи под блокировкой очереди, как и в `resume_task`.
+    /// Задача, возобновленная до сохранения, снова ставится в очередь.
+    async fn store_paused(&self, mut task: Task, control: &TaskControl) {
+        let mut scheduler = self.scheduler.lock().await;
+        let order_hash = task.order_hash.clone();
+        if control.is_paused() {
+            task.set_status(TaskStatus::Paused);
+            task.queue_num = 0;
+            // Заказ приостановленной задачи восстановлен для продолжения
+            let _ = db::insert_active_task(&self.db_pool, &task).await;
+            self.task_heap.write().await.insert(order_hash, task);
+            return;
+        }
+        let token = db::read_token(&self.db_pool, &task.order.token_id)
+            .await
+            .ok()
+            .flatten();
+        task.set_status(TaskStatus::Waiting);
+        scheduler.push(QueueEntry::new(&task, token.as_ref()));
+        let _ = db::insert_active_task(&self.db_pool, &task).await;
+        self.task_heap.write().await.insert(order_hash, task);
+        self.update_queue_nums(&scheduler).await;
+        self.notify.notify_one();
+    }
+
+    /// Позиции задач в очереди: задачи из очереди нумеруются с 1 в порядке, в котором
+    /// их начнут обработчики, у задач в обработке и приостановленных задач 0
+    async fn update_queue_nums(&self, scheduler: &Scheduler) {
+        let positions = scheduler.positions();
+        for (order_hash, task) in self.task_heap.write().await.iter_mut() {
+            task.queue_num = positions.get(order_hash).copied().unwrap_or(0);
+        }
+    }
+
+    pub async fn registering_task(
+        &self,
+        task: Task,
+        token: Option<&Token>,
+    ) -> Result<OrderHash, ApiError> {
+        let mut scheduler = self.scheduler.lock().await;
+        let task_count = active_count(self.task_heap.read().await.values());
         if task_count >= self.queue_limit {
             return Err(ApiError::QueueOverflow(self.queue_limit));
         }
-        task.queue_num = task_count;
         let order_hash = task.order_hash.clone();
-        if !self.task_heap.read().await.contains_key(&order_hash) {
-            self.task_heap
-                .write()
-                .await
-                .insert(order_hash.clone(), task);
-
-            if self.sender.send(order_hash.clone()).await.is_err() {
-                return Err(ApiError::TaskSendFailure);
+        if self.task_heap.read().await.contains_key(&order_hash) {
+            return Err(ApiError::DuplicateTask(order_hash));
+        }
+        let _ = db::insert_active_task(&self.db_pool, &task).await;
+        self.controls
+            .write()
+            .await
+            .insert(order_hash.clone(), TaskControl::default());
+        scheduler.push(QueueEntry::new(&task, token));
+        self.task_heap
+            .write()
+            .await
+            .insert(order_hash.clone(), task);
+        self.update_queue_nums(&scheduler).await;
+        self.notify.notify_one();
+
+        Ok(order_hash)
+    }
+
+    /// Приостановленная задача после перезапуска: в обработчике без постановки в очередь
+    pub async fn restore_paused_task(&self, mut task: Task) {
+        task.queue_num = 0;
+        let order_hash = task.order_hash.clone();
+        self.controls
+            .write()
+            .await
+            .insert(order_hash.clone(), TaskControl::default());
+        self.task_heap.write().await.insert(order_hash, task);
+    }
+
+    /// Отмена задачи: задача из очереди или приостановленная задача завершается сразу,
+    /// выполняющаяся задача - после текущего товара
+    pub async fn cancel_task(&self, key: &String) -> Option<Task> {
+        let mut scheduler = self.scheduler.lock().await;
+        let mut task_heap = self.task_heap.write().await;
+        let mut task = task_heap.get(key)?.clone();
+        if let Some(control) = self.controls.read().await.get(key) {
+            control.cancel();
+        }
+        if matches!(task.status, TaskStatus::Waiting | TaskStatus::Paused) {
+            task_heap.remove(key);
+            drop(task_heap);
+            if scheduler.remove(key) {
+                self.update_queue_nums(&scheduler).await;
             }
-            return Ok(order_hash);
+            self.controls.write().await.remove(key);
+            task.set_status(TaskStatus::Cancelled);
+            let _ = db::complete_task(&self.db_pool, &task).await;
+        }
+
+        Some(task)
+    }
+
+    /// Приостановка задачи: задача из очереди приостанавливается сразу,
+    /// выполняющаяся задача - после текущего товара
+    pub async fn pause_task(&self, key: &String) -> Option<Task> {
+        let mut scheduler = self.scheduler.lock().await;
+        let mut task_heap = self.task_heap.write().await;
+        let task = task_heap.get_mut(key)?;
+        if let Some(control) = self.controls.read().await.get(key) {
+            control.pause();
+        }
+        // Задача, которую уже взял обработчик, приостанавливается в `process_task`
+        if task.status != TaskStatus::Waiting || !scheduler.remove(key) {
+            return Some(task.clone());
         }
+        task.set_status(TaskStatus::Paused);
+        let task = task.clone();
+        drop(task_heap);
+        self.update_queue_nums(&scheduler).await;
+        let _ = db::update_active_task(&self.db_pool, &task).await;
 
-        Err(ApiError::DuplicateTask(order_hash))
+        Some(self.get_task(key).await.unwrap_or(task))
+    }
+
+    /// Возобновление задачи: приостановленная задача ставится в очередь и продолжается
+    /// с текущего шага. Приостановленные задачи не учитываются в лимитах очереди и токена,
+    /// поэтому лимиты проверяются при возобновлении.
+    pub async fn resume_task(&self, key: &String, token: Option<&Token>) -> Result<Task, ApiError> {
+        let mut scheduler = self.scheduler.lock().await;
+        let mut task_heap = self.task_heap.write().await;
+        let paused = task_heap.get(key).ok_or(ApiError::TaskNotFound)?.status == TaskStatus::Paused;
+        if paused {
+            if active_count(task_heap.values()) >= self.queue_limit {
+                return Err(ApiError::QueueOverflow(self.queue_limit));
+            }
+            if let Some(token) = token {
+                let tasks = task_heap.values().filter(|t| t.order.token_id == token.id);
+                if active_count(tasks) >= token.tc_limit {
+                    return Err(ApiError::ConcurrencyLimitExceeded(token.tc_limit));
+                }
+            }
+        }
+        // Приостановка снимается под блокировкой очереди: задачу, которая остановилась,
+        // но еще не сохранена приостановленной, ставит в очередь `store_paused`
+        if let Some(control) = self.controls.read().await.get(key) {
+            control.resume();
+        }
+        let task = task_heap.get_mut(key).ok_or(ApiError::TaskNotFound)?;
+        if !paused {
+            return Ok(task.clone());
+        }
+        task.set_status(TaskStatus::Waiting);
+        scheduler.push(QueueEntry::new(task, token));
+        drop(task_heap);
+        self.update_queue_nums(&scheduler).await;
+        self.notify.notify_one();
+        let task = self.get_task(key).await.ok_or(ApiError::TaskNotFound)?;
+        let _ = db::update_active_task(&self.db_pool, &task).await;
+
+        Ok(task)
     }
 
+    /// Задачи токена в очереди и в обработке, без приостановленных
     #[inline]
     pub async fn task_count_by_token_id(&self, token_id: &str) -> usize {
-        self.task_heap
-            .read()
-            .await
-            .values()
-            .filter(|t| t.order.token_id == token_id)
-            .count()
+        let task_heap = self.task_heap.read().await;
+        let tasks = task_heap.values().filter(|t| t.order.token_id == token_id);
+
+        active_count(tasks) as usize
     }
 
     #[inline]
@@ -124,26 +391,65 @@ impl TaskHandler {
         self.task_heap.read().await.get(key).map(|t| t.clone())
     }
 
-    // pub async fn with_task<R>(&self, key: &String, f: impl FnOnce(&Task) -> R) -> Option<R> {
-    //     self.task_heap.read()
-    //         .await
-    //         .get(key)
-    //         .map(f)
-    // }
-
     #[inline]
     pub async fn len(&self) -> usize {
         self.task_heap.read().await.len()
     }
+}
+
+/// Задачи в очереди и в обработке: приостановленные задачи не учитываются в лимитах
+fn active_count<'a>(tasks: impl Iterator<Item = &'a Task>) -> u64 {
+    tasks.filter(|t| t.status != TaskStatus::Paused).count() as u64
+}
+
+/// Удаление результатов задач, которые не были получены за `completed_task_ttl`:
+/// при запуске и раз в `COMPLETED_TASKS_PURGE_INTERVAL`
+fn spawn_completed_tasks_purge(db_pool: Arc<db::Pool>) {
+    let ttl = cfg::get().api.completed_task_ttl;
+    if ttl == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(COMPLETED_TASKS_PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            match db::purge_completed_tasks(&db_pool, ttl).await {
+                Ok(0) => {}
+                Ok(removed) => {
+                    logger::write(
+                        log::Level::Info,
+                        "PURGE_COMPLETED_TASKS",
+                        format!("removed: {removed}"),
+                    )
+                    .await
+                }
+                Err(e) => {
+                    logger::write(log::Level::Error, "PURGE_COMPLETED_TASKS", e.to_string()).await
+                }
+            }
+        }
+    });
+}
 
-    // pub async fn abort(&self) {
-    //     self.join_handle.abort();
-    // }
+/// Сообщение паники обработчика
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".into())
+}
+
+/// Учет запросов через прокси задачи в инвентаре прокси
+async fn record_proxy_usage(db_pool: &db::Pool, task: &Task) {
+    for (address, stats) in task.proxies.iter() {
+        let _ = db::record_proxy_usage(db_pool, address, stats.requests, stats.failures).await;
+    }
 }
 
 pub struct AppState {
     pub db_pool: Arc<db::Pool>,
-    task_handlers: Vec<TaskHandler>,
+    task_handler: TaskHandler,
     pub handlers_count: usize,
     pub handler_queue_limit: usize,
     pub open_ws_counter: Mutex<u32>,
@@ -158,62 +464,107 @@ impl AppState {
         handler_queue_limit: usize,
         open_ws_limit: u32,
     ) -> Self {
-        let mut task_handlers = Vec::with_capacity(handlers_count);
-        for _ in 0..handlers_count {
-            task_handlers.push(TaskHandler::run(db_pool.clone(), handler_queue_limit).await);
-        }
-        Self {
+        let task_handler = TaskHandler::run(
+            db_pool.clone(),
+            handlers_count,
+            handler_queue_limit * handlers_count,
+        )
+        .await;
+        let app_state = Self {
             db_pool,
-            task_handlers,
+            task_handler,
             handlers_count,
             handler_queue_limit,
             open_ws_counter: Mutex::new(0),
             open_ws_limit,
             cache: Mutex::new(Cache::new()),
+        };
+        app_state.restore_tasks().await;
+        spawn_completed_tasks_purge(app_state.db_pool.clone());
+
+        app_state
+    }
+
+    /// Задачи, сохраненные до перезапуска: задачи из очереди ставятся в очередь заново,
+    /// приостановленные задачи остаются приостановленными, задачи в обработке завершаются
+    /// со статусом `interrupted` и частичным результатом
+    async fn restore_tasks(&self) {
+        let tasks = match db::read_active_tasks(&self.db_pool).await {
+            Ok(tasks) => tasks,
+            Err(e) => {
+                logger::write(log::Level::Error, "RESTORE_TASKS", e.to_string()).await;
+                return;
+            }
+        };
+        let (mut resumed, mut interrupted) = (0, 0);
+        for mut task in tasks {
+            let handler = &self.task_handler;
+            let restored = match task.status {
+                TaskStatus::Waiting => {
+                    let token = db::read_token(&self.db_pool, &task.order.token_id)
+                        .await
+                        .ok()
+                        .flatten();
+                    handler.registering_task(task.clone(), token.as_ref()).await.is_ok()
+                }
+                TaskStatus::Paused => {
+                    handler.restore_paused_task(task.clone()).await;
+                    true
+                }
+                _ => false,
+            };
+            if restored {
+                resumed += 1;
+                continue;
+            }
+            task.set_status(TaskStatus::Interrupted);
+            let _ = db::complete_task(&self.db_pool, &task).await;
+            interrupted += 1;
+        }
+        if resumed + interrupted > 0 {
+            logger::write(
+                log::Level::Info,
+                "RESTORE_TASKS",
+                format!("resumed: {resumed}, interrupted: {interrupted}"),
+            )
+            .await;
         }
     }
 
+    /// Постановка заказа в очередь с приоритетом и весом токена
     #[inline]
-    pub async fn insert_order(&self, order: Order) -> Result<OrderHash, ApiError> {
+    pub async fn insert_order(&self, order: Order, token: &Token) -> Result<OrderHash, ApiError> {
         let task = Task::from_order(order);
-        let handler_index = self.select_handler_index().await;
 
-        self.task_handlers
-            .get(handler_index)
-            .unwrap()
-            .registering_task(task)
-            .await
+        self.task_handler.registering_task(task, Some(token)).await
     }
 
     #[inline]
     pub async fn get_task_count(&self) -> usize {
-        let mut task_count = 0_usize;
-        for handler in self.task_handlers.iter() {
-            task_count += handler.len().await;
-        }
+        self.task_handler.len().await
+    }
 
-        task_count
+    /// Состояние обработчиков задач
+    pub fn handlers_state(&self) -> Vec<HandlerState> {
+        self.task_handler
+            .handlers
+            .lock()
+            .map(|handlers| handlers.clone())
+            .unwrap_or_default()
     }
 
     #[inline]
     pub async fn task_count_by_token_id(&self, token_id: &str) -> usize {
-        let mut task_count = 0_usize;
-        for handler in self.task_handlers.iter() {
-            task_count += handler.task_count_by_token_id(token_id).await;
-        }
-
-        task_count
+        self.task_handler.task_count_by_token_id(token_id).await
     }
 
     #[inline]
     pub async fn get_task_state(&self, order_hash: &String) -> Result<Task, ApiError> {
-        for th in self.task_handlers.iter() {
-            if th.contains_task(order_hash).await {
-                if let Some(task) = th.get_task(order_hash).await {
-                    return Ok(task);
-                }
-                return Err(ApiError::UnknownError);
+        if self.task_handler.contains_task(order_hash).await {
+            if let Some(task) = self.task_handler.get_task(order_hash).await {
+                return Ok(task);
             }
+            return Err(ApiError::UnknownError);
         }
 
         db::cutout_task(&self.db_pool, order_hash)
@@ -221,22 +572,39 @@ impl AppState {
             .map_err(|_| ApiError::TaskNotFound)
     }
 
-    #[inline]
-    async fn select_handler_index(&self) -> usize {
-        if self.handlers_count == 1 {
-            return 0;
-        }
-        let mut task_handlers_queue = Vec::with_capacity(self.handlers_count);
-        for th in self.task_handlers.iter() {
-            task_handlers_queue.push(th.len().await);
-        }
+    /// Отмена задачи в очереди или в обработке владельцем токена
+    pub async fn cancel_task(&self, order_hash: &String, token_id: &str) -> Result<Task, ApiError> {
+        self.check_task_owner(order_hash, token_id).await?;
+        self.task_handler
+            .cancel_task(order_hash)
+            .await
+            .ok_or(ApiError::TaskNotFound)
+    }
+
+    /// Приостановка задачи владельцем токена
+    pub async fn pause_task(&self, order_hash: &String, token_id: &str) -> Result<Task, ApiError> {
+        self.check_task_owner(order_hash, token_id).await?;
+        self.task_handler
+            .pause_task(order_hash)
+            .await
+            .ok_or(ApiError::TaskNotFound)
+    }
 
-        task_handlers_queue
-            .iter()
-            .enumerate()
-            .min_by_key(|(_, value)| *value)
-            .unwrap_or((0, &0))
-            .0
+    /// Возобновление приостановленной задачи владельцем токена
+    pub async fn resume_task(&self, order_hash: &String, token_id: &str) -> Result<Task, ApiError> {
+        self.check_task_owner(order_hash, token_id).await?;
+        let token = db::read_token(&self.db_pool, token_id).await?;
+        self.task_handler
+            .resume_task(order_hash, token.as_ref())
+            .await
+    }
+
+    /// Задача в очереди или в обработке, оформленная токеном `token_id`
+    async fn check_task_owner(&self, order_hash: &String, token_id: &str) -> Result<(), ApiError> {
+        match self.task_handler.get_task(order_hash).await {
+            Some(task) if task.order.token_id == token_id => Ok(()),
+            _ => Err(ApiError::TaskNotFound),
+        }
     }
 
     pub async fn open_websocket(&self) -> Result<(), ApiError> {
@@ -254,3 +622,122 @@ impl AppState {
         *open_ws_counter -= 1;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::api::TaskResult, utils::create_token_id};
+
+    /// Задача с товаром `wb/0` вызывает панику обработчика, остальные задачи завершаются
+    fn process_or_panic(
+        handler: TaskHandler,
+        order_hash: OrderHash,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(async move {
+            let mut task = handler.get_task(&order_hash).await.unwrap();
+            if task.order.products.iter().any(|p| p == "wb/0") {
+                panic!("injected panic");
+            }
+            handler.task_heap.write().await.remove(&order_hash);
+            task.set_status(TaskStatus::Completed);
+            db::complete_task(&handler.db_pool, &task).await.unwrap();
+        })
+    }
+
+    #[tokio::test]
+    async fn test_handler_restart() {
+        let db_pool = Arc::new(db::init().await.unwrap());
+        let handler = TaskHandler::run_with(db_pool.clone(), 1, 10, process_or_panic);
+        let task = |product: &str| {
+            Task::from_order(Order {
+                token_id: create_token_id(),
+                products: vec![product.into()],
+                ..Default::default()
+            })
+        };
+        let (failing, next) = (task("wb/0"), task("wb/1"));
+        handler.registering_task(failing.clone(), None).await.unwrap();
+        handler.registering_task(next.clone(), None).await.unwrap();
+        for _ in 0..50 {
+            if handler.len().await == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        // Задача упавшего обработчика завершена с ошибкой, очередь обрабатывается дальше
+        let failed = db::cutout_task(&db_pool, &failing.order_hash).await.unwrap();
+        assert_eq!(failed.status, TaskStatus::Error);
+        let Some(TaskResult::Error(error)) = failed.result else {
+            panic!("no task error");
+        };
+        assert_eq!(error["error"], "TaskHandlerFailure");
+        assert!(error["message"].as_str().unwrap().contains("injected panic"));
+        let completed = db::cutout_task(&db_pool, &next.order_hash).await.unwrap();
+        assert_eq!(completed.status, TaskStatus::Completed);
+
+        let state = handler.handlers.lock().unwrap()[0].clone();
+        assert!(state.alive);
+        assert_eq!(state.restarts, 1);
+        assert_eq!(state.last_error.as_deref(), Some("injected panic"));
+        assert!(state.order_hash.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pause_resume() {
+        let db_pool = Arc::new(db::init().await.unwrap());
+        // Без обработчиков: задачи остаются в очереди
+        let handler = TaskHandler::run_with(db_pool, 0, 2, process_or_panic);
+        let mut token = Token::new(60, 10, 1);
+        let task = |product: &str| {
+            Task::from_order(Order {
+                token_id: token.id.clone(),
+                products: vec![product.into()],
+                ..Default::default()
+            })
+        };
+        let (first, second) = (task("wb/1"), task("wb/2"));
+        let key = first.order_hash.clone();
+        handler.registering_task(first, Some(&token)).await.unwrap();
+
+        // Задача из очереди приостанавливается сразу и не занимает лимиты
+        let paused = handler.pause_task(&key).await.unwrap();
+        assert_eq!(paused.status, TaskStatus::Paused);
+        assert_eq!(handler.scheduler.lock().await.len(), 0);
+        assert_eq!(handler.task_count_by_token_id(&token.id).await, 0);
+        handler.registering_task(second, Some(&token)).await.unwrap();
+
+        // Лимит токена проверяется при возобновлении
+        let error = handler.resume_task(&key, Some(&token)).await.unwrap_err();
+        assert!(matches!(error, ApiError::ConcurrencyLimitExceeded(1)));
+        token.tc_limit = 2;
+        let resumed = handler.resume_task(&key, Some(&token)).await.unwrap();
+        assert_eq!(resumed.status, TaskStatus::Waiting);
+        assert_eq!(resumed.queue_num, 2);
+
+        // Возобновление до сохранения приостановки: задача снова ставится в очередь
+        let control = handler.controls.read().await[&key].clone();
+        handler.scheduler.lock().await.remove(&key);
+        control.pause();
+        handler.resume_task(&key, Some(&token)).await.unwrap();
+        let task = handler.get_task(&key).await.unwrap();
+        handler.store_paused(task, &control).await;
+        assert_eq!(handler.get_task(&key).await.unwrap().status, TaskStatus::Waiting);
+        assert_eq!(handler.scheduler.lock().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_panic_message() {
+        let result = tokio::spawn(async { panic!("browser crashed") }).await;
+        let e = result.unwrap_err();
+        assert!(e.is_panic());
+        assert_eq!(panic_message(e.into_panic()), "browser crashed");
+
+        let index = 3;
+        let result = tokio::spawn(async move { panic!("index {index}") }).await;
+        assert_eq!(panic_message(result.unwrap_err().into_panic()), "index 3");
+
+        let error = ApiError::TaskHandlerFailure(panic_message(Box::new(1)));
+        assert_eq!(error.to_json()["code"], 505);
+    }
+}
@@ -85,6 +85,7 @@ pub struct Api {
     pub root_api_path: String,
     pub description_file_path: Option<String>,
     pub log_file_path: Option<String>,
+    pub rules_file_path: Option<String>,
     pub assets_path: String,
     pub db_path: String,
     pub db_max_conn: u32,
@@ -94,7 +95,42 @@ pub struct Api {
     pub open_ws_limit: u32,
     pub test_token: TestToken,
     pub interrupt_check_step: u64,
+    /// Минимальный интервал сохранения прогресса задачи в базу, мс
+    #[serde(default = "default_task_persist_interval")]
+    pub task_persist_interval: u64,
+    /// Время хранения результатов завершенных задач, которые не были получены, с.
+    /// 0 - хранить без ограничения
+    #[serde(default = "default_completed_task_ttl")]
+    pub completed_task_ttl: u64,
     pub available_markets: Vec<String>,
+    #[serde(default)]
+    pub rate_limit: RateLimit,
+}
+
+fn default_task_persist_interval() -> u64 {
+    1000
+}
+
+fn default_completed_task_ttl() -> u64 {
+    7 * 24 * 60 * 60
+}
+
+/// Ограничение частоты запросов к маркетплейсам, общее для всех обработчиков
+#[derive(Deserialize, Serialize, Default, Debug, Clone, ToSchema)]
+#[serde(default)]
+pub struct RateLimit {
+    /// Лимит маркетплейсов без своего лимита, без него запросы не ограничиваются
+    pub default: Option<MarketRateLimit>,
+    /// Отдельный лимит для каждого прокси маркетплейса
+    pub per_proxy: bool,
+    pub markets: HashMap<String, MarketRateLimit>,
+}
+
+/// Token bucket: `rps` запросов в секунду с накоплением до `burst` запросов
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, ToSchema)]
+pub struct MarketRateLimit {
+    pub rps: f64,
+    pub burst: u32,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
@@ -119,6 +155,65 @@ pub struct ReqSession {
     pub close_tabs_interval: u64,
     pub launch_sleep: u64,
     pub timings: ReqTimings,
+    #[serde(default)]
+    pub proxy_health: ProxyHealth,
+    #[serde(default)]
+    pub proxy_rotation: ProxyRotation,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// Количество одновременных запросов товаров маркетплейса в задаче, по умолчанию 1
+    #[serde(default)]
+    pub parallelism: HashMap<String, usize>,
+    /// Количество товаров маркетплейса в одном пакетном запросе, если маркетплейс
+    /// поддерживает пакетные запросы, по умолчанию 1
+    #[serde(default)]
+    pub batch_size: HashMap<String, usize>,
+}
+
+/// Повтор запроса товара после временной ошибки (ошибка сети, HTTP 429 и 5xx) или капчи
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Количество попыток, 1 - без повторов
+    pub max_attempts: u32,
+    /// Задержка перед второй попыткой, дальше удваивается
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+    /// Доля задержки, на которую она случайно уменьшается
+    pub jitter: f64,
+    pub retry_captcha: bool,
+    /// Менять прокси перед повтором при любой стратегии смены прокси
+    pub switch_proxy: bool,
+}
+
+/// Стратегия смены прокси из пула маркетплейса
+#[derive(Deserialize, Serialize, Default, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ProxyRotation {
+    /// Следующий прокси пула каждые `set_proxy_interval` запросов и после неудачного запроса
+    #[default]
+    RoundRobin,
+    /// Случайный прокси каждые `set_proxy_interval` запросов и после неудачного запроса
+    Random,
+    /// Давно не использованный прокси каждые `set_proxy_interval` запросов
+    /// и после неудачного запроса
+    LeastRecentlyUsed,
+    /// Один прокси на маркетплейс, пока он доступен
+    Sticky,
+    /// Следующий прокси пула только после неудачного запроса
+    OnFailure,
+    /// Случайный прокси с весом по доле успешных запросов каждые `set_proxy_interval`
+    /// запросов и после неудачного запроса
+    Weighted,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
+#[serde(default)]
+pub struct ProxyHealth {
+    pub quarantine_failures: u32,
+    pub quarantine_ms: u64,
+    pub evict_quarantines: u32,
+    pub check_url: String,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
@@ -172,6 +267,7 @@ impl Default for Api {
             root_api_path: "/api/v1".into(),
             description_file_path: None,
             log_file_path: None,
+            rules_file_path: None,
             assets_path: "assets".into(),
             db_path: "sqlite:scraper_api.db".into(),
             db_max_conn: 2,
@@ -181,7 +277,10 @@ impl Default for Api {
             open_ws_limit: 20,
             test_token: TestToken::default(),
             interrupt_check_step: 60,
+            task_persist_interval: default_task_persist_interval(),
+            completed_task_ttl: default_completed_task_ttl(),
             available_markets: vec!["oz".into(), "wb".into(), "ym".into(), "mm".into()],
+            rate_limit: RateLimit::default(),
         }
     }
 }
@@ -225,6 +324,35 @@ impl Default for ReqSession {
             close_tabs_interval: 40,
             launch_sleep: 700,
             timings: ReqTimings::default(),
+            proxy_health: ProxyHealth::default(),
+            proxy_rotation: ProxyRotation::default(),
+            retry: RetryPolicy::default(),
+            parallelism: HashMap::new(),
+            batch_size: HashMap::new(),
+        }
+    }
+}
+
+impl Default for ProxyHealth {
+    fn default() -> Self {
+        Self {
+            quarantine_failures: 3,
+            quarantine_ms: 60000,
+            evict_quarantines: 3,
+            check_url: "http://ip-api.com/json".into(),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay_ms: 500,
+            max_delay_ms: 8000,
+            jitter: 0.5,
+            retry_captcha: true,
+            switch_proxy: true,
         }
     }
 }
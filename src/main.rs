@@ -33,6 +33,7 @@ async fn init() {
     let config_ = config::get();
     log::info!("{:#?}\n", config_);
     config::init();
+    scraper::extractor::rules::init();
     utils::print_logo();
     api::logger::init().await;
     log::info!(
@@ -1,10 +1,16 @@
+use browser_bridge::PageStatus;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+use reqwest::Url;
+use std::collections::HashMap;
+
 use crate::{
     api::error::ApiError,
-    models::scraper::ProductData,
+    config::ProxyRotation,
+    models::scraper::{ProductData, ProductDiagnostics, ProxyStats, SellerData},
+    scraper::market,
     utils::{create_token_id, remove_duplicates, sha1_hash, timestamp_now},
 };
 
@@ -29,6 +35,26 @@ pub struct Token {
     #[serde(rename = "taskCountLimit")]
     /// Лимит токена на количество параллельных обработок заказа
     pub tc_limit: u64,
+
+    #[serde(rename = "proxyGroup", skip_serializing_if = "Option::is_none")]
+    /// Группа прокси сервера для заказов без `proxyPool`
+    pub proxy_group: Option<String>,
+
+    #[serde(default)]
+    /// Приоритет заказов токена в очереди: заказы с большим приоритетом начинаются раньше
+    pub priority: u32,
+
+    #[serde(default = "default_token_weight")]
+    /// Вес токена: доля обработки относительно других токенов с тем же приоритетом
+    pub weight: u32,
+
+    #[serde(default)]
+    /// Токену разрешены срочные заказы (`express`)
+    pub express: bool,
+}
+
+fn default_token_weight() -> u32 {
+    1
 }
 
 impl Token {
@@ -39,6 +65,10 @@ impl Token {
             ttl,
             op_limit,
             tc_limit,
+            proxy_group: None,
+            priority: 0,
+            weight: default_token_weight(),
+            express: false,
         }
     }
 
@@ -55,6 +85,10 @@ pub enum TaskStatus {
     Completed,
     Interrupted,
     Error,
+    /// Отменена владельцем токена, результат частичный
+    Cancelled,
+    /// Приостановлена, продолжается с текущего шага после возобновления
+    Paused,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -73,19 +107,65 @@ pub struct Order {
 
     pub products: Vec<String>,
 
+    /// Парсинг выдачи маркетплейса вместо списка товаров
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub listing: Option<OrderListing>,
+
     #[serde(rename = "proxyPool")]
     pub proxy_pool: Vec<String>,
 
-    //#[serde(rename="proxyMap")]
-    //pub proxy_map: HashMap<String, Vec<String>>,
+    /// Пулы прокси по символам маркетплейсов, для остальных маркетплейсов `proxyPool`
+    #[serde(rename = "proxyMap")]
+    pub proxy_map: HashMap<String, Vec<String>>,
+
+    /// Стратегия смены прокси, по умолчанию из конфигурации
+    #[serde(rename = "proxyRotation", skip_serializing_if = "Option::is_none")]
+    pub proxy_rotation: Option<ProxyRotation>,
+
     pub cookies: Vec<OrderCookieParam>,
+
+    /// Записывать диагностику обработки каждого товара
+    pub diagnostics: bool,
+
+    /// Возвращать расширенные поля ProductData
+    pub extended: bool,
+
+    /// Запрашивать характеристики товаров
+    pub characteristics: bool,
+
+    /// Запрашивать отзывы о товарах
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reviews: Option<OrderReviews>,
+
+    /// Срочный заказ: начинается раньше несрочных заказов независимо от приоритета токена
+    pub express: bool,
 }
 
 impl Order {
     fn sha1_hash(&self) -> OrderHash {
         let mut sort_data = self.products.clone();
         sort_data.sort();
-        let order_hash_data = format!("{}.{}", self.token_id, sort_data.join(","));
+        let mut order_hash_data = format!("{}.{}", self.token_id, sort_data.join(","));
+        if let Some(listing) = &self.listing {
+            order_hash_data.push('.');
+            order_hash_data.push_str(&serde_json::to_string(listing).unwrap_or_default());
+        }
+        if let Some(reviews) = &self.reviews {
+            order_hash_data.push('.');
+            order_hash_data.push_str(&serde_json::to_string(reviews).unwrap_or_default());
+        }
+        // Параметры, меняющие результат заказа
+        for (name, enabled) in [
+            ("diagnostics", self.diagnostics),
+            ("extended", self.extended),
+            ("characteristics", self.characteristics),
+            ("express", self.express),
+        ] {
+            if enabled {
+                order_hash_data.push('.');
+                order_hash_data.push_str(name);
+            }
+        }
 
         sha1_hash(order_hash_data.as_bytes())
     }
@@ -97,6 +177,96 @@ impl Order {
         if !self.proxy_pool.is_empty() {
             remove_duplicates(&mut self.proxy_pool);
         }
+        for proxy_pool in self.proxy_map.values_mut() {
+            remove_duplicates(proxy_pool);
+        }
+    }
+}
+
+/// Выдача маркетплейса: поисковый запрос (`market` и `query`), ссылка на категорию (`url`)
+/// или витрина продавца (`seller`)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(default)]
+pub struct OrderListing {
+    /// Символ маркетплейса, для ссылки на категорию определяется по ссылке
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// Продавец в формате `символ маркетплейса/идентификатор продавца`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seller: Option<String>,
+
+    /// Количество страниц выдачи
+    pub pages: u64,
+
+    /// Максимальное количество товаров, по умолчанию лимит токена
+    pub limit: u64,
+
+    /// Запросить данные найденных товаров, как в заказе со списком товаров
+    pub scrape: bool,
+}
+
+impl Default for OrderListing {
+    fn default() -> Self {
+        Self {
+            market: None,
+            query: None,
+            url: None,
+            seller: None,
+            pages: 1,
+            limit: 0,
+            scrape: false,
+        }
+    }
+}
+
+impl OrderListing {
+    /// Ссылка на страницу выдачи с номером `page` (с 1)
+    pub fn page_url(&self, page: u64) -> Option<String> {
+        let market = market::get(self.market.as_deref()?)?;
+        if let Some(seller_id) = self.seller_id() {
+            return market.seller_listing_url(seller_id, page);
+        }
+        match (&self.query, &self.url) {
+            (Some(query), _) => market.search_url(query, page),
+            (None, Some(url)) => market.category_url(&Url::parse(url).ok()?, page),
+            (None, None) => None,
+        }
+    }
+
+    /// Идентификатор продавца для заказа витрины продавца
+    pub fn seller_id(&self) -> Option<&str> {
+        self.seller
+            .as_deref()
+            .and_then(|seller| seller.split_once('/'))
+            .map(|(_, id)| id)
+    }
+}
+
+/// Отзывы о товарах заказа: не больше `limit` на товар, не раньше даты `since`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(default)]
+pub struct OrderReviews {
+    /// Максимальное количество отзывов на товар
+    pub limit: u64,
+
+    /// Дата в формате `YYYY-MM-DD`, более ранние отзывы не возвращаются
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+}
+
+impl Default for OrderReviews {
+    fn default() -> Self {
+        Self {
+            limit: 100,
+            since: None,
+        }
     }
 }
 
@@ -127,9 +297,15 @@ pub struct OrderCookieParam {
 #[derive(Clone, Debug, Default)]
 pub struct OrderExtractData {
     pub products: Vec<String>,
+    pub listing: Option<OrderListing>,
     pub proxy_pool: Vec<String>,
-    //pub proxy_map: HashMap<String, Vec<String>>,
+    pub proxy_map: HashMap<String, Vec<String>>,
+    pub proxy_rotation: Option<ProxyRotation>,
     pub cookies: Vec<OrderCookieParam>,
+    pub diagnostics: bool,
+    pub extended: bool,
+    pub characteristics: bool,
+    pub reviews: Option<OrderReviews>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
@@ -152,6 +328,31 @@ pub struct Task {
     #[schema(schema_with = serde_json::Value::default)]
     pub result: Option<TaskResult>,
 
+    /// Диагностика обработки товаров, если она включена в заказе
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(schema_with = serde_json::Value::default)]
+    pub diagnostics: Option<IndexMap<String, ProductDiagnostics>>,
+
+    /// Профиль продавца для заказа витрины продавца
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(schema_with = serde_json::Value::default)]
+    pub seller: Option<SellerData>,
+
+    /// Причины отсутствия данных: классификация страниц товаров (`blocked`, `captcha`, `notFound`)
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    #[schema(schema_with = serde_json::Value::default)]
+    pub outcomes: IndexMap<String, PageStatus>,
+
+    /// Количество попыток запроса товаров, запрошенных больше одного раза
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    #[schema(schema_with = serde_json::Value::default)]
+    pub attempts: IndexMap<String, u32>,
+
+    /// Статистика запросов через прокси заказа (без учетных данных)
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    #[schema(schema_with = serde_json::Value::default)]
+    pub proxies: IndexMap<String, ProxyStats>,
+
     #[serde(rename = "createdAt")]
     pub created_at: u64,
 }
@@ -189,6 +390,11 @@ impl Task {
             status: TaskStatus::Waiting,
             progress: None,
             result: None,
+            diagnostics: None,
+            seller: None,
+            outcomes: IndexMap::new(),
+            attempts: IndexMap::new(),
+            proxies: IndexMap::new(),
             created_at: timestamp_now(),
         }
     }
@@ -196,7 +402,10 @@ impl Task {
     pub fn is_done_by_status(&self) -> bool {
         matches!(
             self.status,
-            TaskStatus::Completed | TaskStatus::Error | TaskStatus::Interrupted
+            TaskStatus::Completed
+                | TaskStatus::Error
+                | TaskStatus::Interrupted
+                | TaskStatus::Cancelled
         )
     }
 
@@ -215,34 +424,65 @@ impl Task {
     pub fn extract_order_data(&mut self) -> OrderExtractData {
         let extract_data = OrderExtractData {
             products: std::mem::take(&mut self.order.products),
+            listing: self.order.listing.clone(),
             proxy_pool: std::mem::take(&mut self.order.proxy_pool),
-            //proxy_map: std::mem::take(&mut self.order.proxy_map),
+            proxy_map: std::mem::take(&mut self.order.proxy_map),
+            proxy_rotation: self.order.proxy_rotation,
             cookies: std::mem::take(&mut self.order.cookies),
+            diagnostics: self.order.diagnostics,
+            extended: self.order.extended,
+            characteristics: self.order.characteristics,
+            reviews: self.order.reviews.clone(),
         };
 
         extract_data
     }
 
-    pub fn extract_result_data(&mut self) -> Option<&IndexMap<String, Option<ProductData>>> {
-        if let Some(TaskResult::Data(ref_data)) = &self.result {
-            return Some(ref_data);
+    pub fn insert_result_item(&mut self, k: String, v: Option<ProductData>) {
+        if let Some(TaskResult::Data(items_map)) = &mut self.result {
+            items_map.insert(k, v);
         }
+    }
 
-        None
+    pub fn insert_outcome(&mut self, k: String, v: PageStatus) {
+        if !v.is_ok() {
+            self.outcomes.insert(k, v);
+        }
     }
 
-    pub fn insert_result_item(&mut self, k: String, v: Option<ProductData>) {
-        if let Some(TaskResult::Data(items_map)) = &mut self.result {
+    pub fn insert_attempts(&mut self, k: String, v: u32) {
+        if v > 1 {
+            self.attempts.insert(k, v);
+        }
+    }
+
+    pub fn init_diagnostics(&mut self) {
+        self.diagnostics = Some(IndexMap::new())
+    }
+
+    pub fn insert_diagnostics_item(&mut self, k: String, v: ProductDiagnostics) {
+        if let Some(items_map) = &mut self.diagnostics {
             items_map.insert(k, v);
         }
     }
 
+    /// Сводка диагностики для журнала. Забирает диагностику из задачи.
+    pub fn take_diagnostics_summary(&mut self) -> Option<DiagnosticsSummary> {
+        self.diagnostics
+            .take()
+            .map(|items| DiagnosticsSummary::from_items(items.values()))
+    }
+
     pub fn set_progress(&mut self, done: u64, total: u64) {
         self.progress = Some(TaskProgress::new(done, total));
     }
 
+    /// Прогресс по товарам заказа, для заказа выдачи - по страницам выдачи
     pub fn init_progress(&mut self) {
-        let total = self.order.products.len() as u64;
+        let total = match &self.order.listing {
+            Some(listing) => listing.pages,
+            None => self.order.products.len() as u64,
+        };
         self.set_progress(0, total);
     }
 
@@ -269,6 +509,118 @@ impl Task {
     }
 }
 
+/// Сводка диагностики обработки товаров задачи
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsSummary {
+    pub products: u64,
+    pub avg_elapsed_ms: u64,
+    /// Количество страниц блокировки, капчи и отсутствующих товаров
+    pub outcomes: IndexMap<PageStatus, u64>,
+    /// Количество ответов по HTTP статусам
+    pub statuses: IndexMap<u16, u64>,
+    /// Количество ошибок запроса по типам
+    pub fetch_errors: IndexMap<String, u64>,
+    /// Количество ошибок разбора контента по типам
+    pub errors: IndexMap<String, u64>,
+    /// Количество товаров, у которых не удалось извлечь поле
+    pub failed_fields: IndexMap<String, u64>,
+}
+
+impl DiagnosticsSummary {
+    pub fn from_items<'a>(items: impl Iterator<Item = &'a ProductDiagnostics>) -> Self {
+        let mut summary = Self::default();
+        let mut elapsed_ms = 0;
+        for item in items {
+            summary.products += 1;
+            elapsed_ms += item.fetch.elapsed_ms;
+            if !item.status.is_ok() {
+                *summary.outcomes.entry(item.status).or_default() += 1;
+            }
+            if let Some(status) = item.fetch.status {
+                *summary.statuses.entry(status).or_default() += 1;
+            }
+            if let Some(e) = &item.fetch.error {
+                *summary.fetch_errors.entry(e.clone()).or_default() += 1;
+            }
+            if let Some(e) = &item.error {
+                *summary.errors.entry(e.clone()).or_default() += 1;
+            }
+            for field in item.fields.keys() {
+                *summary.failed_fields.entry(field.clone()).or_default() += 1;
+            }
+        }
+        summary.avg_elapsed_ms = elapsed_ms.checked_div(summary.products).unwrap_or(0);
+
+        summary
+    }
+}
+
+/// Прокси из хранилища сервера
+#[derive(Clone, Debug, PartialEq, sqlx::FromRow, Serialize, Deserialize, ToSchema)]
+pub struct ProxyRecord {
+    pub id: i64,
+
+    pub url: String,
+
+    /// Адрес прокси без учетных данных: `http://192.0.2.10:8000`
+    pub address: String,
+
+    #[sqlx(rename = "group_name")]
+    pub group: String,
+
+    pub disabled: bool,
+
+    /// Количество запросов через прокси
+    pub requests: u64,
+
+    /// Количество неудачных запросов через прокси
+    pub failures: u64,
+
+    #[serde(rename = "createdAt")]
+    pub created_at: u64,
+
+    #[serde(rename = "lastUsedAt", skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<u64>,
+}
+
+/// Результат проверки прокси
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, ToSchema)]
+pub struct ProxyCheck {
+    /// Сервис внешнего IP доступен через прокси
+    pub ok: bool,
+
+    /// HTTP статус ответа (известен только для запросов без браузера)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+
+    #[serde(rename = "latencyMs")]
+    pub latency_ms: u64,
+
+    /// Внешний IP-адрес прокси
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+
+    /// Код страны внешнего IP-адреса
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+
+    /// Авторизация на прокси, если у прокси есть учетные данные
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Проверка прокси запросом без браузера и через браузер
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, ToSchema)]
+pub struct ProxyCheckReport {
+    pub reqwest: ProxyCheck,
+
+    pub browser: ProxyCheck,
+}
+
 /// Состояние API
 #[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -283,4 +635,81 @@ pub struct ApiState {
     pub open_ws_limit: u32,
     /// Открыто WebSockets на данный момент
     pub curr_open_ws: u32,
+    /// Лимиты запросов к маркетплейсам (`<symbol>` или `<symbol>@<proxy>`)
+    #[schema(schema_with = serde_json::Value::default)]
+    pub rate_limits: IndexMap<String, RateLimitState>,
+    /// Состояние обработчиков задач
+    pub handlers: Vec<HandlerState>,
+}
+
+/// Состояние обработчика задач
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HandlerState {
+    /// Обработчик работает
+    pub alive: bool,
+    /// Перезапусков после аварийного завершения
+    pub restarts: u32,
+    /// Задача в обработке
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_hash: Option<String>,
+    /// Причина последнего аварийного завершения
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// Состояние лимита запросов к маркетплейсу
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitState {
+    pub rps: f64,
+    pub burst: u32,
+    /// Запросов доступно без ожидания
+    pub tokens: f64,
+    /// Ожидание очереди следующего запроса
+    pub wait_ms: u64,
+    /// Запросов с момента запуска сервера
+    pub requests: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_hash() {
+        let order = Order {
+            products: vec!["wb/1".into(), "wb/2".into()],
+            ..Default::default()
+        };
+        let hash = order.sha1_hash();
+        let reversed = Order {
+            products: vec!["wb/2".into(), "wb/1".into()],
+            ..order.clone()
+        };
+        assert_eq!(reversed.sha1_hash(), hash);
+
+        // Заказы с разными параметрами результата не совпадают
+        let extended = Order {
+            extended: true,
+            ..order.clone()
+        };
+        assert_ne!(extended.sha1_hash(), hash);
+        let characteristics = Order {
+            characteristics: true,
+            ..order.clone()
+        };
+        assert_ne!(characteristics.sha1_hash(), hash);
+        assert_ne!(characteristics.sha1_hash(), extended.sha1_hash());
+        let diagnostics = Order {
+            diagnostics: true,
+            ..order.clone()
+        };
+        assert_ne!(diagnostics.sha1_hash(), hash);
+        let express = Order {
+            express: true,
+            ..order
+        };
+        assert_ne!(express.sha1_hash(), hash);
+    }
 }
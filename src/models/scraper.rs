@@ -1,11 +1,16 @@
-use serde::{Deserialize, Serialize};
+use browser_bridge::PageStatus;
+use indexmap::IndexMap;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::{collections::HashMap, sync::LazyLock};
 use utoipa::ToSchema;
 // use rand::Rng;
 
 use crate::{
     config as cfg,
-    scraper::error::ScraperError,
+    scraper::{
+        error::ScraperError,
+        market::{self, MarketExtractor},
+    },
     // utils::{
     //     select_random_product_name,
     //     select_random_vendor,
@@ -17,43 +22,22 @@ pub static AVAILABLE_MARKETS: LazyLock<Vec<String>> =
     LazyLock::new(|| cfg::get().api.available_markets.clone());
 
 pub static MARKET_MAP: LazyLock<HashMap<String, Market>> = LazyLock::new(|| {
-    HashMap::from([
-        (
-            "oz".into(),
-            Market {
-                name: "Ozon".into(),
-                url: "https://ozon.ru".into(),
-                available: AVAILABLE_MARKETS.contains(&"oz".into()),
-            },
-        ),
-        (
-            "wb".into(),
-            Market {
-                name: "Wildberries".into(),
-                url: "https://www.wildberries.ru/".into(),
-                available: AVAILABLE_MARKETS.contains(&"wb".into()),
-            },
-        ),
-        (
-            "ym".into(),
-            Market {
-                name: "YandexMarket".into(),
-                url: "https://market.yandex.ru/".into(),
-                available: AVAILABLE_MARKETS.contains(&"ym".into()),
-            },
-        ),
-        (
-            "mm".into(),
-            Market {
-                name: "MegaMarket".into(),
-                url: "https://megamarket.ru/".into(),
-                available: AVAILABLE_MARKETS.contains(&"mm".into()),
-            },
-        ),
-    ])
+    market::all()
+        .map(|m| {
+            (
+                m.symbol().into(),
+                Market {
+                    name: m.name().into(),
+                    url: m.url().into(),
+                    available: AVAILABLE_MARKETS.contains(&m.symbol().into()),
+                },
+            )
+        })
+        .collect()
 });
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
 pub struct ProductData {
     pub sku: String,
 
@@ -85,6 +69,61 @@ pub struct ProductData {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub brand: Option<String>,
+
+    // Расширенные поля, возвращаются только для заказов с `extended`
+    #[serde(rename = "oldPrice", skip_serializing_if = "Option::is_none")]
+    pub old_price: Option<u64>,
+
+    /// Скидка в процентах
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discount: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stock: Option<u64>,
+
+    /// Дата или срок доставки в формате маркетплейса
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delivery: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub categories: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub images: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variants: Option<Vec<ProductVariant>>,
+
+    /// Характеристики товара, только для заказов с `characteristics`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub characteristics: Option<IndexMap<String, String>>,
+
+    /// Отзывы о товаре, только для заказов с `reviews`
+    #[serde(rename = "reviewItems", skip_serializing_if = "Option::is_none")]
+    pub review_items: Option<Vec<ReviewData>>,
+}
+
+/// Вариант товара (размер, цвет, комплектация)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct ProductVariant {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sku: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stock: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available: Option<bool>,
 }
 
 impl ProductData {
@@ -105,6 +144,31 @@ impl ProductData {
         .all(|is_none| *is_none)
     }
 
+    /// Расчет скидки по старой цене и наличия по остатку, если маркетплейс их не указывает
+    pub fn fill_derived(&mut self) {
+        if let (None, Some(old_price), Some(price)) = (self.discount, self.old_price, self.price) {
+            if old_price > price {
+                let discount = (old_price - price) as f64 / old_price as f64 * 100.0;
+                self.discount = Some(discount.round() as u64);
+            }
+        }
+        if let (None, Some(stock)) = (self.available, self.stock) {
+            self.available = Some(stock > 0);
+        }
+    }
+
+    /// Убирает расширенные поля для заказов без `extended`
+    pub fn strip_extended(&mut self) {
+        self.old_price = None;
+        self.discount = None;
+        self.available = None;
+        self.stock = None;
+        self.delivery = None;
+        self.categories = None;
+        self.images = None;
+        self.variants = None;
+    }
+
     #[inline]
     pub fn to_option(self) -> Option<ProductData> {
         if self.is_empty() {
@@ -146,33 +210,176 @@ impl ProductData {
     // }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// Данные страницы выдачи маркетплейса (поиск, категория)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct ListingData {
+    /// Товары выдачи. Идентификатор товара определяется по `url` или `sku`
+    pub products: Option<Vec<ProductData>>,
+}
+
+/// Данные страницы отзывов о товаре
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct ReviewsData {
+    /// Отзывы страницы в порядке маркетплейса (сначала новые)
+    pub reviews: Option<Vec<ReviewData>>,
+}
+
+/// Отзыв о товаре
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct ReviewData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+
+    /// Оценка от 1 до 5
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stars: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+
+    /// Дата публикации в формате `YYYY-MM-DD`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+
+    /// Есть ли в отзыве фотографии
+    pub photos: bool,
+}
+
+/// Профиль продавца маркетплейса
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct SellerData {
+    pub id: String,
+
+    /// Ссылка на витрину продавца
+    pub url: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Юридическое наименование
+    #[serde(rename = "legalName", skip_serializing_if = "Option::is_none")]
+    pub legal_name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inn: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ogrn: Option<String>,
+
+    /// Юридический адрес
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rating: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reviews: Option<u64>,
+}
+
+/// Диагностика обработки товара (режим `diagnostics` заказа)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct ProductDiagnostics {
+    pub status: PageStatus,
+
+    pub fetch: FetchInfo,
+
+    /// Причина, по которой контент не удалось разобрать целиком
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// Поля, которые не удалось извлечь, и причины
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub fields: IndexMap<String, String>,
+}
+
+/// Результат запроса страницы товара
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct FetchInfo {
+    /// HTTP статус ответа (известен только для запросов без браузера)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+
+    /// Размер контента в байтах
+    pub bytes: usize,
+
+    #[serde(rename = "elapsedMs")]
+    pub elapsed_ms: u64,
+
+    /// Количество товаров в пакетном запросе
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch: Option<usize>,
+
+    /// Ошибка запроса
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Состояние прокси в сессии запросов
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
-pub enum Symbol {
-    OZ,
-    WB,
-    YM,
-    MM,
+pub enum ProxyState {
+    #[default]
+    Active,
+    /// Временно не используется после серии неудачных запросов
+    Quarantined,
+    /// Исключен до конца задачи
+    Evicted,
 }
 
+/// Статистика запросов через прокси за время обработки задачи
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct ProxyStats {
+    pub requests: u64,
+    pub successes: u64,
+    pub failures: u64,
+
+    #[serde(rename = "avgLatencyMs")]
+    pub avg_latency_ms: u64,
+
+    pub state: ProxyState,
+}
+
+/// Символ маркетплейса, зарегистрированного в [`market`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Symbol(&'static str);
+
 impl Symbol {
     pub fn from_string(s: &str) -> Result<Self, ScraperError> {
-        match s.to_lowercase().as_str() {
-            "oz" => Ok(Self::OZ),
-            "wb" => Ok(Self::WB),
-            "ym" => Ok(Self::YM),
-            "mm" => Ok(Self::MM),
-            _ => Err(ScraperError::InvalidSymbol),
-        }
+        market::get(s)
+            .map(|m| Self(m.symbol()))
+            .ok_or(ScraperError::InvalidSymbol)
     }
 
     pub fn as_str(&self) -> &'static str {
-        match self {
-            Self::OZ => "oz",
-            Self::WB => "wb",
-            Self::YM => "ym",
-            Self::MM => "mm",
-        }
+        self.0
+    }
+
+    pub fn market(&self) -> &'static dyn MarketExtractor {
+        market::get(self.0).expect("Symbol is always registered")
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::from_string(&s).map_err(de::Error::custom)
     }
 }
 
@@ -195,53 +402,17 @@ impl Product {
     pub fn from_string_without_valid(s: &str) -> Self {
         let (symbol, id) = s.split_once('/').unwrap();
         let symbol = Symbol::from_string(symbol).unwrap();
-
-        let url = match symbol {
-            Symbol::OZ => format!("https://www.ozon.ru/product/{}", id),
-            Symbol::WB => format!("https://www.wildberries.ru/catalog/{}/detail.aspx", id),
-            Symbol::YM => {
-                let parts = id.splitn(3, '-').collect::<Vec<_>>();
-                format!(
-                    "https://market.yandex.ru/product/{}?sku={}&uniqueId={}",
-                    parts[0], parts[1], parts[2],
-                )
-            }
-            Symbol::MM => format!("https://megamarket.ru/promo-page/details/#?slug={}", id),
-        };
-        let sku = match symbol {
-            Symbol::YM => id.splitn(3, '-').nth(1).unwrap().into(),
-            _ => id.to_string(),
-        };
+        let market = symbol.market();
 
         Self {
             id: id.into(),
             symbol,
-            sku,
-            url,
+            sku: market.sku(id),
+            url: market.product_url(id),
         }
     }
 
     pub fn get_parse_url(&self) -> String {
-        match self.symbol {
-            Symbol::OZ => format!(
-                "https://www.ozon.ru/api/entrypoint-api.bx/page/json/v2?url=/product/{}/",
-                self.id
-            ),
-            Symbol::WB => format!(
-                "https://card.wb.ru/cards/v2/detail?appType=1&curr=rub&dest=-1257218&nm={}",
-                self.id
-            ),
-            Symbol::YM => {
-                let parts = self.id.splitn(3, '-').collect::<Vec<_>>();
-                format!(
-                    "https://market.yandex.ru/product/{}?sku={}&uniqueId={}",
-                    parts[0], parts[1], parts[2],
-                )
-            }
-            Symbol::MM => format!(
-                "https://megamarket.ru/promo-page/details/#?slug={}",
-                self.id
-            ),
-        }
+        self.symbol.market().parse_url(&self.id)
     }
 }
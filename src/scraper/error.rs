@@ -11,6 +11,20 @@ pub enum ScraperError {
     // ParseProductError,
 }
 
+#[derive(Error, Debug)]
+pub enum RulesError {
+    #[error("Failed to read rules file: {0}")]
+    Read(String),
+    #[error("Failed to parse rules: {0}")]
+    Parse(String),
+    #[error("Invalid CSS selector {0}")]
+    InvalidSelector(String),
+    #[error("Invalid regex {0}")]
+    InvalidRegex(String),
+    #[error("Invalid rule {0}")]
+    InvalidRule(String),
+}
+
 #[derive(Error, Debug)]
 pub enum ReqSessionError {
     #[error("BrowserError: {0}")]
@@ -23,6 +37,18 @@ pub enum ReqSessionError {
     RequestSending,
     #[error("Error extracting the response content")]
     ExtractResponseContent,
+    #[error("All proxies of the pool are evicted")]
+    ProxyPoolExhausted,
+}
+
+impl ReqSessionError {
+    /// Ошибка, после которой запрос можно повторить
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::Browser(_) | Self::RequestSending | Self::ExtractResponseContent
+        )
+    }
 }
 
 impl From<BrowserError> for ReqSessionError {
@@ -0,0 +1,61 @@
+use browser_bridge::PageStatus;
+use indexmap::IndexMap;
+use reqwest::Url;
+
+use super::rules::{self, CompiledRules, Extracted};
+use crate::models::scraper::{ListingData, ProductData, Symbol};
+
+pub const LISTING_PAGE: &str = "listing";
+
+/// Товары, найденные на странице выдачи
+#[derive(Debug, Default)]
+pub struct Listing {
+    pub status: PageStatus,
+    /// Товары по ключу `<symbol>/<id>` в порядке выдачи
+    pub products: IndexMap<String, ProductData>,
+    /// Поля, которые не удалось извлечь, и причины
+    pub failures: IndexMap<String, String>,
+}
+
+pub fn extract_listing(symbol: Symbol, content: &str) -> Result<Listing, String> {
+    extract_listing_with(&rules::get(), symbol, content)
+}
+
+pub fn extract_listing_with(
+    rules: &CompiledRules,
+    symbol: Symbol,
+    content: &str,
+) -> Result<Listing, String> {
+    let extracted = rules.extract_page(symbol.as_str(), LISTING_PAGE, content)?;
+    let market = symbol.market();
+    let base_url = Url::parse(market.product_url_prefix()).ok();
+    let mut products = IndexMap::new();
+    for mut pd in extracted.to_data::<ListingData>().products.unwrap_or_default() {
+        // Ссылки в выдаче обычно относительные, в выдаче бывают ссылки не на товары
+        let id_from_url = Url::options()
+            .base_url(base_url.as_ref())
+            .parse(&pd.url)
+            .ok()
+            .filter(|url| url.as_str().starts_with(market.product_url_prefix()))
+            .and_then(|url| market.id_from_url(&url))
+            .filter(|id| market.validate_id(id));
+        let id = match id_from_url {
+            Some(id) => id,
+            None if market.validate_id(&pd.sku) => std::mem::take(&mut pd.sku),
+            None => continue,
+        };
+        pd.sku = market.sku(&id);
+        pd.url = market.product_url(&id);
+        market.finalize(&mut pd, &Extracted::default());
+        pd.fill_derived();
+        products
+            .entry(format!("{}/{id}", symbol.as_str()))
+            .or_insert(pd);
+    }
+
+    Ok(Listing {
+        status: extracted.status,
+        products,
+        failures: extracted.failures,
+    })
+}
@@ -1,2 +1,5 @@
+pub mod listing;
 pub mod product;
-mod selectors;
+pub mod reviews;
+pub mod rules;
+pub mod seller;
@@ -0,0 +1,88 @@
+use browser_bridge::PageStatus;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+
+use super::rules::{self, CompiledRules};
+use crate::models::scraper::{Product, ReviewData, ReviewsData};
+
+pub const REVIEWS_PAGE: &str = "reviews";
+
+/// Отзывы, извлеченные со страницы отзывов о товаре
+#[derive(Debug, Default)]
+pub struct Reviews {
+    pub status: PageStatus,
+    pub reviews: Vec<ReviewData>,
+}
+
+pub fn extract_reviews(product: &Product, content: &str) -> Result<Reviews, String> {
+    extract_reviews_with(&rules::get(), product, content)
+}
+
+pub fn extract_reviews_with(
+    rules: &CompiledRules,
+    product: &Product,
+    content: &str,
+) -> Result<Reviews, String> {
+    let extracted = rules.extract_page(product.symbol.as_str(), REVIEWS_PAGE, content)?;
+    let mut reviews = extracted
+        .to_data::<ReviewsData>()
+        .reviews
+        .unwrap_or_default();
+    for review in reviews.iter_mut() {
+        review.date = review.date.as_deref().and_then(normalize_date);
+    }
+
+    Ok(Reviews {
+        status: extracted.status,
+        reviews,
+    })
+}
+
+/// Начала названий месяцев: "мар" проверяется раньше "ма" (май)
+const MONTHS: [&str; 12] = [
+    "янв", "фев", "мар", "апр", "ма", "июн", "июл", "авг", "сен", "окт", "ноя", "дек",
+];
+
+/// Дата отзыва в формате `YYYY-MM-DD` из форматов маркетплейсов:
+/// ISO 8601, timestamp в секундах, `DD.MM.YYYY` и `12 марта 2024` (без года - текущий год)
+pub fn normalize_date(s: &str) -> Option<String> {
+    let s = s.trim();
+    if let Ok(timestamp) = s.parse::<i64>() {
+        return DateTime::from_timestamp(timestamp, 0).map(|dt| dt.date_naive().to_string());
+    }
+    let iso = s
+        .get(..10)
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+    let date = if let Some(date) = iso {
+        date
+    } else if let Ok(date) = NaiveDate::parse_from_str(s, "%d.%m.%Y") {
+        date
+    } else {
+        let mut parts = s.split_whitespace();
+        let day = parts.next()?.parse::<u32>().ok()?;
+        let month_name = parts.next()?.to_lowercase();
+        let month = MONTHS.iter().position(|m| month_name.starts_with(m))?;
+        let year = match parts.next() {
+            Some(year) => year.parse::<i32>().ok()?,
+            None => Utc::now().year(),
+        };
+        NaiveDate::from_ymd_opt(year, month as u32 + 1, day)?
+    };
+
+    Some(date.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_date() {
+        assert_eq!(normalize_date("2024-05-12T10:11:12Z").as_deref(), Some("2024-05-12"));
+        assert_eq!(normalize_date("1715508672").as_deref(), Some("2024-05-12"));
+        assert_eq!(normalize_date("12.05.2024").as_deref(), Some("2024-05-12"));
+        assert_eq!(normalize_date("12 мая 2024").as_deref(), Some("2024-05-12"));
+        assert_eq!(normalize_date("3 марта 2023").as_deref(), Some("2023-03-03"));
+        assert_eq!(normalize_date("1 сентября 2022").as_deref(), Some("2022-09-01"));
+        assert!(normalize_date("вчера").is_none());
+    }
+}
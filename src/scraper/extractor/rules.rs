@@ -0,0 +1,974 @@
+use browser_bridge::PageStatus;
+use indexmap::IndexMap;
+use regex::Regex;
+use scraper_dep::{ElementRef, Html, Selector};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, LazyLock, RwLock},
+};
+
+use super::super::{
+    super::{
+        config as cfg,
+        models::scraper::{ListingData, ProductData, ReviewsData, SellerData},
+        utils::{read_file, write_to_file},
+    },
+    error::RulesError,
+    market,
+};
+use super::{
+    listing::LISTING_PAGE, product::CHARACTERISTICS_PAGE, reviews::REVIEWS_PAGE, seller::SELLER_PAGE,
+};
+
+const DEFAULT_RULES: &str = include_str!("../../../Rules.toml");
+
+static RULES: LazyLock<RwLock<Arc<CompiledRules>>> = LazyLock::new(|| {
+    let rules = load(true).unwrap_or_else(|e| {
+        log::error!("Extraction rules error!\n {e}");
+        panic!();
+    });
+    RwLock::new(Arc::new(rules))
+});
+
+/// Проверяет правила извлечения при запуске сервиса
+pub fn init() {
+    let rules = get();
+    log::info!("[EXTRACTION_RULES] markets: {:?}", rules.symbols());
+}
+
+pub fn get() -> Arc<CompiledRules> {
+    RULES.read().unwrap().clone()
+}
+
+/// Перечитывает файл правил. При ошибке остаются действующие правила.
+pub fn reload() -> Result<Arc<CompiledRules>, RulesError> {
+    let rules = Arc::new(load(false)?);
+    *RULES.write().unwrap() = rules.clone();
+
+    Ok(rules)
+}
+
+/// Правила из `rules_file_path`, без файла - встроенные правила. Файл со встроенными
+/// правилами создается только при запуске (`write_default`) и только для пути `.toml`.
+fn load(write_default: bool) -> Result<CompiledRules, RulesError> {
+    let rules = match &cfg::get().api.rules_file_path {
+        Some(path) if Path::new(path).exists() => {
+            let content = read_file(path).map_err(|e| RulesError::Read(e.to_string()))?;
+            Rules::parse(&content, path.ends_with(".json"))?
+        }
+        Some(path) => {
+            log::warn!("{path} is not exists! Default extraction rules are used");
+            if write_default && path.ends_with(".toml") {
+                let _ = write_to_file(path, DEFAULT_RULES.as_bytes());
+            }
+            Rules::parse(DEFAULT_RULES, false)?
+        }
+        None => Rules::parse(DEFAULT_RULES, false)?,
+    };
+    rules.check_markets(market::all().map(|m| m.symbol()))?;
+
+    rules.compile()
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct Rules(pub IndexMap<String, MarketRules>);
+
+impl Rules {
+    pub fn parse(content: &str, is_json: bool) -> Result<Self, RulesError> {
+        if is_json {
+            serde_json::from_str(content).map_err(|e| RulesError::Parse(e.to_string()))
+        } else {
+            toml::from_str(content).map_err(|e| RulesError::Parse(e.to_string()))
+        }
+    }
+
+    /// Правила должны быть у каждого зарегистрированного маркетплейса
+    pub fn check_markets<'a>(
+        &self,
+        symbols: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), RulesError> {
+        for symbol in symbols {
+            if !self.0.contains_key(symbol) {
+                return Err(RulesError::InvalidRule(format!("{symbol}: missing market rules")));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn compile(self) -> Result<CompiledRules, RulesError> {
+        let mut markets = HashMap::with_capacity(self.0.len());
+        for (symbol, market_rules) in self.0.iter() {
+            markets.insert(
+                symbol.clone(),
+                market_rules.compile(symbol, check_field::<ProductData>)?,
+            );
+        }
+
+        Ok(CompiledRules {
+            source: self,
+            markets,
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentFormat {
+    Html,
+    Json,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MarketRules {
+    pub format: ContentFormat,
+    pub root: Option<String>,
+    pub json: Option<String>,
+    #[serde(default)]
+    pub detect: DetectRules,
+    pub fields: IndexMap<String, FieldRule>,
+    /// Правила дополнительных страниц маркетплейса (`characteristics`, ...)
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub pages: IndexMap<String, MarketRules>,
+}
+
+/// Признаки страниц без данных о товаре: CSS селекторы (html) или JSON pointers (json)
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct DetectRules {
+    pub captcha: Vec<String>,
+    pub blocked: Vec<String>,
+    pub not_found: Vec<String>,
+}
+
+impl DetectRules {
+    fn iter(&self) -> impl Iterator<Item = (PageStatus, &String)> {
+        let with = |status| move |marker| (status, marker);
+        self.captcha
+            .iter()
+            .map(with(PageStatus::Captcha))
+            .chain(self.blocked.iter().map(with(PageStatus::Blocked)))
+            .chain(self.not_found.iter().map(with(PageStatus::NotFound)))
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    #[default]
+    String,
+    Int,
+    Float,
+    Bool,
+    /// Словарь из списка (`many`) объектов с полями `key` и `value`
+    Map,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct FieldRule {
+    pub selector: Option<String>,
+    pub last: bool,
+    pub attr: Option<String>,
+    pub pointer: Option<String>,
+    pub replace: Vec<String>,
+    pub regex: Option<String>,
+    #[serde(rename = "type")]
+    pub field_type: FieldType,
+    pub scale: Option<f64>,
+    pub round: Option<u32>,
+    pub many: bool,
+    pub item: Option<String>,
+    pub fields: IndexMap<String, FieldRule>,
+}
+
+pub struct CompiledRules {
+    pub source: Rules,
+    markets: HashMap<String, CompiledMarketRules>,
+}
+
+impl CompiledRules {
+    pub fn symbols(&self) -> Vec<&str> {
+        self.source.0.keys().map(|k| k.as_str()).collect()
+    }
+
+    pub fn extract(&self, symbol: &str, content: &str) -> Result<Extracted, String> {
+        self.markets
+            .get(symbol)
+            .ok_or_else(|| format!("no extraction rules for '{symbol}'"))?
+            .extract(content)
+    }
+
+    /// Извлечение по правилам дополнительной страницы маркетплейса
+    pub fn extract_page(&self, symbol: &str, page: &str, content: &str) -> Result<Extracted, String> {
+        self.markets
+            .get(symbol)
+            .and_then(|m| m.pages.get(page))
+            .ok_or_else(|| format!("no extraction rules for '{symbol}.pages.{page}'"))?
+            .extract(content)
+    }
+}
+
+struct CompiledMarketRules {
+    format: ContentFormat,
+    root_selector: Option<Selector>,
+    root_pointer: Option<String>,
+    json_selector: Option<Selector>,
+    detect_selectors: Vec<(PageStatus, Selector)>,
+    detect_pointers: Vec<(PageStatus, String)>,
+    fields: Vec<(String, CompiledFieldRule)>,
+    pages: HashMap<String, CompiledMarketRules>,
+}
+
+struct CompiledFieldRule {
+    selector: Option<Selector>,
+    last: bool,
+    attr: Option<String>,
+    pointer: Option<String>,
+    replace: Vec<String>,
+    regex: Option<Regex>,
+    field_type: FieldType,
+    scale: Option<f64>,
+    round: Option<u32>,
+    many: bool,
+    item: Option<String>,
+    fields: Vec<(String, CompiledFieldRule)>,
+}
+
+fn parse_selector(symbol: &str, name: &str, s: &str) -> Result<Selector, RulesError> {
+    Selector::parse(s).map_err(|_| RulesError::InvalidSelector(format!("{symbol}.{name}: '{s}'")))
+}
+
+impl MarketRules {
+    fn compile(&self, symbol: &str, check: FieldCheck) -> Result<CompiledMarketRules, RulesError> {
+        let is_html = self.format == ContentFormat::Html;
+        let is_json_content = !is_html || self.json.is_some();
+        let (root_selector, root_pointer) = match &self.root {
+            Some(root) if is_html => (Some(parse_selector(symbol, "root", root)?), None),
+            Some(root) => (None, Some(root.clone())),
+            None => (None, None),
+        };
+        if self.json.is_some() && !is_html {
+            return Err(RulesError::InvalidRule(format!(
+                "{symbol}: 'json' is only available for html format"
+            )));
+        }
+        let json_selector = match &self.json {
+            Some(s) => Some(parse_selector(symbol, "json", s)?),
+            None => None,
+        };
+
+        let mut detect_selectors = Vec::new();
+        let mut detect_pointers = Vec::new();
+        for (status, marker) in self.detect.iter() {
+            if is_html {
+                detect_selectors.push((status, parse_selector(symbol, "detect", marker)?));
+            } else {
+                detect_pointers.push((status, marker.clone()));
+            }
+        }
+
+        let mut fields = Vec::with_capacity(self.fields.len());
+        for (name, rule) in self.fields.iter() {
+            let path = format!("{symbol}.{name}");
+            let compiled = rule.compile(&path, is_json_content, false)?;
+            check(name, rule)
+                .map_err(|_| RulesError::InvalidRule(format!("{path}: incompatible type")))?;
+            fields.push((name.clone(), compiled));
+        }
+
+        let mut pages = HashMap::with_capacity(self.pages.len());
+        for (page, page_rules) in self.pages.iter() {
+            let path = format!("{symbol}.pages.{page}");
+            if !page_rules.pages.is_empty() {
+                return Err(RulesError::InvalidRule(format!(
+                    "{path}: nested 'pages' are not supported"
+                )));
+            }
+            let check = page_field_check(page)
+                .ok_or_else(|| RulesError::InvalidRule(format!("{path}: unknown page")))?;
+            pages.insert(page.clone(), page_rules.compile(&path, check)?);
+        }
+
+        Ok(CompiledMarketRules {
+            format: self.format,
+            root_selector,
+            root_pointer,
+            json_selector,
+            detect_selectors,
+            detect_pointers,
+            fields,
+            pages,
+        })
+    }
+}
+
+impl FieldRule {
+    /// `nested` - правило поля элемента списка (`fields`): для html `selector`
+    /// не обязателен, без него значение берется из самого элемента
+    fn compile(
+        &self,
+        path: &str,
+        is_json_content: bool,
+        nested: bool,
+    ) -> Result<CompiledFieldRule, RulesError> {
+        let invalid = |msg: &str| RulesError::InvalidRule(format!("{path}: {msg}"));
+        if is_json_content && self.selector.is_some() {
+            return Err(invalid("'selector' is not available for JSON content"));
+        }
+        if !is_json_content && !nested && self.selector.is_none() {
+            return Err(invalid("'selector' is required for html content"));
+        }
+        if is_json_content && self.pointer.is_none() {
+            return Err(invalid("'pointer' is required for JSON content"));
+        }
+        if !self.many && (self.item.is_some() || !self.fields.is_empty()) {
+            return Err(invalid("'item' and 'fields' are only available with 'many'"));
+        }
+        if self.item.is_some() && !self.fields.is_empty() {
+            return Err(invalid("'item' and 'fields' cannot be used together"));
+        }
+        if self.field_type == FieldType::Map
+            && !(self.many && self.fields.contains_key("key") && self.fields.contains_key("value"))
+        {
+            return Err(invalid("'map' requires 'many' and 'fields' 'key' and 'value'"));
+        }
+        let mut fields = Vec::with_capacity(self.fields.len());
+        for (name, rule) in self.fields.iter() {
+            if rule.many {
+                return Err(invalid("nested 'many' is not supported"));
+            }
+            let path = format!("{path}.{name}");
+            fields.push((name.clone(), rule.compile(&path, is_json_content, true)?));
+        }
+
+        Ok(CompiledFieldRule {
+            selector: match &self.selector {
+                Some(s) => Some(
+                    Selector::parse(s)
+                        .map_err(|_| RulesError::InvalidSelector(format!("{path}: '{s}'")))?,
+                ),
+                None => None,
+            },
+            last: self.last,
+            attr: self.attr.clone(),
+            pointer: self.pointer.clone(),
+            replace: self.replace.clone(),
+            regex: match &self.regex {
+                Some(r) => Some(
+                    Regex::new(r)
+                        .map_err(|_| RulesError::InvalidRegex(format!("{path}: '{r}'")))?,
+                ),
+                None => None,
+            },
+            field_type: self.field_type,
+            scale: self.scale,
+            round: self.round,
+            many: self.many,
+            item: self.item.clone(),
+            fields,
+        })
+    }
+
+    /// Пример значения, которое правило может извлечь
+    fn sample(&self) -> Value {
+        let item = if self.fields.is_empty() {
+            match self.field_type {
+                FieldType::String => Value::from("0"),
+                FieldType::Int => Value::from(1_u64),
+                FieldType::Float => Value::from(1.5_f64),
+                FieldType::Bool => Value::from(true),
+                FieldType::Map => unreachable!("'map' requires nested fields"),
+            }
+        } else if self.field_type == FieldType::Map {
+            return Value::Object(Map::from_iter([("0".into(), Value::from("0"))]));
+        } else {
+            Value::Object(
+                self.fields
+                    .iter()
+                    .map(|(name, rule)| (name.clone(), rule.sample()))
+                    .collect(),
+            )
+        };
+        if self.many {
+            Value::Array(vec![item])
+        } else {
+            item
+        }
+    }
+}
+
+type FieldCheck = fn(&str, &FieldRule) -> Result<(), serde_json::Error>;
+
+/// Проверяет, что тип правила совместим с одноименным полем данных страницы
+fn check_field<T: DeserializeOwned>(name: &str, rule: &FieldRule) -> Result<(), serde_json::Error> {
+    let mut map = Map::new();
+    map.insert(name.into(), rule.sample());
+    serde_json::from_value::<T>(Value::Object(map)).map(|_| ())
+}
+
+/// Данные, которые извлекаются с дополнительной страницы маркетплейса
+fn page_field_check(page: &str) -> Option<FieldCheck> {
+    match page {
+        CHARACTERISTICS_PAGE => Some(check_field::<ProductData>),
+        LISTING_PAGE => Some(check_field::<ListingData>),
+        SELLER_PAGE => Some(check_field::<SellerData>),
+        REVIEWS_PAGE => Some(check_field::<ReviewsData>),
+        _ => None,
+    }
+}
+
+/// Значения полей, извлеченные по правилам маркетплейса,
+/// и причины, по которым остальные поля извлечь не удалось.
+/// Если контент распознан по признакам `detect`, поля не извлекаются.
+#[derive(Debug, Default)]
+pub struct Extracted {
+    pub status: PageStatus,
+    pub values: Map<String, Value>,
+    pub failures: IndexMap<String, String>,
+}
+
+impl Extracted {
+    pub fn get_u64(&self, name: &str) -> Option<u64> {
+        self.values.get(name).and_then(|v| v.as_u64())
+    }
+
+    pub fn to_product_data(&self) -> ProductData {
+        self.to_data()
+    }
+
+    pub fn to_data<T: DeserializeOwned + Default>(&self) -> T {
+        serde_json::from_value(Value::Object(self.values.clone())).unwrap_or_default()
+    }
+
+    fn insert(&mut self, name: &str, value: Result<Value, String>) {
+        match value {
+            Ok(value) => {
+                self.values.insert(name.into(), value);
+            }
+            Err(reason) => {
+                self.failures.insert(name.into(), reason);
+            }
+        }
+    }
+}
+
+impl CompiledMarketRules {
+    fn extract(&self, content: &str) -> Result<Extracted, String> {
+        let mut extracted = Extracted::default();
+        match self.format {
+            ContentFormat::Json => {
+                let json = serde_json::from_str::<Value>(content)
+                    .map_err(|e| format!("content is not valid JSON: {e}"))?;
+                if let Some((status, _)) = self
+                    .detect_pointers
+                    .iter()
+                    .find(|(_, pointer)| resolve_pointer(&json, pointer).is_ok())
+                {
+                    extracted.status = *status;
+                    return Ok(extracted);
+                }
+                self.extract_json(&json, &mut extracted)?;
+            }
+            ContentFormat::Html => {
+                let html = Html::parse_document(content);
+                if let Some((status, _)) = self
+                    .detect_selectors
+                    .iter()
+                    .find(|(_, selector)| html.select(selector).next().is_some())
+                {
+                    extracted.status = *status;
+                    return Ok(extracted);
+                }
+                let root = match &self.root_selector {
+                    Some(selector) => Some(
+                        html.select(selector)
+                            .next()
+                            .ok_or("root element not found")?,
+                    ),
+                    None => None,
+                };
+                if let Some(json_selector) = &self.json_selector {
+                    let json_text = match root {
+                        Some(root) => root.select(json_selector).next(),
+                        None => html.select(json_selector).next(),
+                    }
+                    .map(|v| v.text().collect::<String>())
+                    .ok_or("JSON element not found")?;
+                    let json = serde_json::from_str::<Value>(&json_text)
+                        .map_err(|e| format!("JSON element is not valid JSON: {e}"))?;
+                    self.extract_json(&json, &mut extracted)?;
+                } else {
+                    let root = root.unwrap_or_else(|| html.root_element());
+                    for (name, rule) in self.fields.iter() {
+                        extracted.insert(name, rule.extract_html(root));
+                    }
+                }
+            }
+        }
+
+        Ok(extracted)
+    }
+
+    fn extract_json(&self, json: &Value, extracted: &mut Extracted) -> Result<(), String> {
+        let root = match &self.root_pointer {
+            Some(pointer) => resolve_pointer(json, pointer).map_err(|e| format!("root {e}"))?,
+            None => json.clone(),
+        };
+        for (name, rule) in self.fields.iter() {
+            extracted.insert(name, rule.extract_json(&root));
+        }
+
+        Ok(())
+    }
+}
+
+impl CompiledFieldRule {
+    fn extract_html(&self, root: ElementRef) -> Result<Value, String> {
+        let Some(selector) = &self.selector else {
+            return self.element_value(root);
+        };
+        let mut elements = root.select(selector);
+        if self.many {
+            return self.collect(elements.map(|element| self.element_value(element)));
+        }
+        let element = if self.last {
+            elements.last()
+        } else {
+            elements.next()
+        }
+        .ok_or("selector not matched")?;
+
+        self.element_value(element)
+    }
+
+    fn element_value(&self, element: ElementRef) -> Result<Value, String> {
+        if !self.fields.is_empty() {
+            return collect_object(
+                self.fields
+                    .iter()
+                    .map(|(name, rule)| (name, rule.extract_html(element))),
+            );
+        }
+        let raw = match &self.attr {
+            Some(attr) => element
+                .attr(attr)
+                .ok_or_else(|| format!("attribute '{attr}' not found"))?
+                .to_string(),
+            None => element.text().collect::<String>(),
+        };
+        let value = match &self.pointer {
+            Some(pointer) => {
+                let json = serde_json::from_str::<Value>(&raw)
+                    .map_err(|e| format!("element value is not valid JSON: {e}"))?;
+                resolve_pointer(&json, pointer)?
+            }
+            None => Value::String(raw),
+        };
+
+        self.convert(value)
+    }
+
+    fn extract_json(&self, root: &Value) -> Result<Value, String> {
+        let pointer = self.pointer.as_ref().ok_or("pointer is not set")?;
+        if !self.many {
+            return self.convert(resolve_pointer(root, pointer)?);
+        }
+        let items = resolve_pointer_all(root, pointer)?;
+
+        self.collect(items.iter().map(|item| {
+            if !self.fields.is_empty() {
+                return collect_object(
+                    self.fields
+                        .iter()
+                        .map(|(name, rule)| (name, rule.extract_json(item))),
+                );
+            }
+            match &self.item {
+                Some(pointer) => resolve_pointer(item, pointer).and_then(|v| self.convert(v)),
+                None => self.convert(item.clone()),
+            }
+        }))
+    }
+
+    fn convert(&self, value: Value) -> Result<Value, String> {
+        let value = match value {
+            Value::String(mut s) => {
+                for pattern in self.replace.iter() {
+                    s = s.replace(pattern.as_str(), "");
+                }
+                if let Some(regex) = &self.regex {
+                    s = regex
+                        .captures(&s)
+                        .and_then(|caps| caps.get(1).or(caps.get(0)))
+                        .ok_or_else(|| format!("regex not matched: '{s}'"))?
+                        .as_str()
+                        .to_string();
+                }
+                Value::String(s.trim().to_string())
+            }
+            value => value,
+        };
+        let number = |value: &Value| -> Result<f64, String> {
+            let n = match value {
+                Value::Number(n) => n.as_f64(),
+                Value::String(s) => s
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect::<String>()
+                    .replace(',', ".")
+                    .parse::<f64>()
+                    .ok(),
+                _ => None,
+            }
+            .ok_or_else(|| format!("not a number: {value}"))?;
+            Ok(n * self.scale.unwrap_or(1.0))
+        };
+
+        match self.field_type {
+            FieldType::String => match value {
+                Value::String(s) if s.is_empty() => Err("empty value".into()),
+                Value::String(s) => Ok(Value::String(s)),
+                Value::Number(n) => Ok(Value::String(n.to_string())),
+                Value::Bool(b) => Ok(Value::String(b.to_string())),
+                value => Err(format!("not a string: {value}")),
+            },
+            FieldType::Int => number(&value).and_then(|n| {
+                if n < 0.0 {
+                    return Err(format!("negative number: {n}"));
+                }
+                Ok(Value::from(n as u64))
+            }),
+            FieldType::Float => number(&value).map(|n| match self.round {
+                Some(round) => {
+                    let k = 10_f64.powi(round as i32);
+                    Value::from((n * k).round() / k)
+                }
+                None => Value::from(n),
+            }),
+            FieldType::Map => Err("'map' is only available with 'many'".into()),
+            FieldType::Bool => match value {
+                Value::Bool(b) => Ok(Value::Bool(b)),
+                Value::Number(n) => Ok(Value::Bool(n.as_f64() != Some(0.0))),
+                Value::String(s) if s == "true" || s == "false" => Ok(Value::Bool(s == "true")),
+                // Количество (фото, вариантов) - признак их наличия
+                value @ Value::String(_) => number(&value).map(|n| Value::Bool(n != 0.0)),
+                Value::Array(items) => Ok(Value::Bool(!items.is_empty())),
+                value => Err(format!("not a bool: {value}")),
+            },
+        }
+    }
+}
+
+impl CompiledFieldRule {
+    /// Список значений или словарь для `map`
+    fn collect(&self, values: impl Iterator<Item = Result<Value, String>>) -> Result<Value, String> {
+        let values = collect_values(values)?;
+        if self.field_type != FieldType::Map {
+            return Ok(values);
+        }
+        let map = values
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|item| {
+                let key = item.get("key")?.as_str()?.trim();
+                let value = match item.get("value")? {
+                    Value::String(s) => s.trim().to_string(),
+                    value => value.to_string(),
+                };
+                (!key.is_empty()).then(|| (key.to_string(), Value::String(value)))
+            })
+            .collect::<Map<_, _>>();
+        if map.is_empty() {
+            return Err("no values".into());
+        }
+
+        Ok(Value::Object(map))
+    }
+}
+
+/// Значения элементов списка без тех, которые не удалось извлечь
+fn collect_values(values: impl Iterator<Item = Result<Value, String>>) -> Result<Value, String> {
+    let values = values.filter_map(|v| v.ok()).collect::<Vec<_>>();
+    if values.is_empty() {
+        return Err("no values".into());
+    }
+
+    Ok(Value::Array(values))
+}
+
+/// Объект из извлеченных полей элемента списка
+fn collect_object<'a>(
+    fields: impl Iterator<Item = (&'a String, Result<Value, String>)>,
+) -> Result<Value, String> {
+    let map = fields
+        .filter_map(|(name, value)| value.ok().map(|v| (name.clone(), v)))
+        .collect::<Map<_, _>>();
+    if map.is_empty() {
+        return Err("no values".into());
+    }
+
+    Ok(Value::Object(map))
+}
+
+/// JSON pointer с поддержкой `*` (первое значение) и `prefix*` (первый ключ с префиксом).
+/// Строковые значения, содержащие JSON, разбираются при переходе внутрь них.
+/// Ошибка содержит сегмент, на котором pointer не разрешился.
+pub fn resolve_pointer(json: &Value, pointer: &str) -> Result<Value, String> {
+    let mut curr = json.clone();
+    for segment in pointer.split('/').filter(|s| !s.is_empty()) {
+        let not_found = || format!("pointer '{pointer}': '{segment}' not found");
+        if let Value::String(s) = &curr {
+            curr = serde_json::from_str::<Value>(s).map_err(|_| not_found())?;
+        }
+        curr = match &curr {
+            Value::Object(map) => match segment.strip_suffix('*') {
+                Some(prefix) => map
+                    .iter()
+                    .find(|(k, _)| k.starts_with(prefix))
+                    .map(|(_, v)| v.clone()),
+                None => map.get(segment).cloned(),
+            },
+            Value::Array(list) => match segment {
+                "*" => list.first().cloned(),
+                _ => segment
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| list.get(i).cloned()),
+            },
+            _ => None,
+        }
+        .ok_or_else(not_found)?;
+    }
+
+    Ok(curr)
+}
+
+/// Значения по JSON pointer для списков (`many`). Сегмент `[]` перебирает все элементы
+/// массива, массив в конце pointer разворачивается в элементы.
+pub fn resolve_pointer_all(json: &Value, pointer: &str) -> Result<Vec<Value>, String> {
+    let Some((head, tail)) = pointer.split_once("/[]") else {
+        return Ok(match resolve_pointer(json, pointer)? {
+            Value::Array(list) => list,
+            value => vec![value],
+        });
+    };
+    let Value::Array(list) = resolve_pointer(json, head)? else {
+        return Err(format!("pointer '{head}': not an array"));
+    };
+    if tail.is_empty() {
+        return Ok(list);
+    }
+
+    Ok(list
+        .iter()
+        .filter_map(|item| resolve_pointer_all(item, tail).ok())
+        .flatten()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn compile(content: &str) -> CompiledRules {
+        Rules::parse(content, false).unwrap().compile().unwrap()
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let rules = compile(DEFAULT_RULES);
+        assert_eq!(rules.symbols(), vec!["oz", "wb", "ym", "mm"]);
+        assert!(rules.source.check_markets(market::all().map(|m| m.symbol())).is_ok());
+
+        let mut rules = Rules::parse(DEFAULT_RULES, false).unwrap();
+        rules.0.shift_remove("oz");
+        assert!(rules.check_markets(market::all().map(|m| m.symbol())).is_err());
+    }
+
+    #[test]
+    fn test_resolve_pointer() {
+        let json = json!({
+            "widgetStates": {
+                "webPrice-3121879-default-1": "{\"price\":\"1 299 ₽\"}",
+            },
+            "list": [{"a": 1}, {"a": 2}],
+        });
+        assert_eq!(
+            resolve_pointer(&json, "/widgetStates/webPrice-*/price"),
+            Ok(json!("1 299 ₽"))
+        );
+        assert_eq!(resolve_pointer(&json, "/list/1/a"), Ok(json!(2)));
+        assert_eq!(resolve_pointer(&json, "/list/*/a"), Ok(json!(1)));
+        assert!(resolve_pointer(&json, "/list/5/a").is_err_and(|e| e.contains("'5'")));
+
+        let json = json!({"groups": [{"items": [1, 2]}, {"items": [3]}, {}]});
+        assert_eq!(
+            resolve_pointer_all(&json, "/groups/[]/items"),
+            Ok(vec![json!(1), json!(2), json!(3)])
+        );
+        assert_eq!(resolve_pointer_all(&json, "/groups/0/items/[]"), Ok(vec![json!(1), json!(2)]));
+    }
+
+    #[test]
+    fn test_html_rules() {
+        let rules = compile(
+            r#"
+            [mm]
+            format = "html"
+            root = "main"
+            [mm.fields.name]
+            selector = "h1"
+            [mm.fields.price]
+            selector = "meta"
+            attr = "content"
+            type = "int"
+            [mm.fields.reviews]
+            selector = "span.reviews"
+            regex = '^(.+)\s\S+$'
+            type = "int"
+            [mm.fields.brand]
+            selector = "span.category"
+            last = true
+            "#,
+        );
+        let html = r#"<main><h1> Набор </h1><meta content="1990">
+            <span class="reviews">1 234 отзыва</span>
+            <span class="category">Инструменты</span><span class="category">Brand</span></main>"#;
+        let pd = rules.extract("mm", html).unwrap().to_product_data();
+        assert_eq!(pd.name.as_deref(), Some("Набор"));
+        assert_eq!(pd.price, Some(1990));
+        assert_eq!(pd.reviews, Some(1234));
+        assert_eq!(pd.brand.as_deref(), Some("Brand"));
+
+        let extracted = rules.extract("mm", "<main><h1>Набор</h1></main>").unwrap();
+        assert_eq!(extracted.values.len(), 1);
+        assert_eq!(
+            extracted.failures.get("price").map(|v| v.as_str()),
+            Some("selector not matched")
+        );
+        assert!(rules.extract("mm", "<div></div>").is_err());
+    }
+
+    #[test]
+    fn test_list_rules() {
+        let rules = compile(
+            r#"
+            [mm]
+            format = "html"
+            [mm.fields.images]
+            selector = "img"
+            attr = "src"
+            many = true
+            [mm.fields.available]
+            selector = "meta"
+            attr = "content"
+            type = "bool"
+            [mm.fields.variants]
+            selector = "li.variant"
+            many = true
+            [mm.fields.variants.fields.sku]
+            attr = "data-sku"
+            [mm.fields.variants.fields.price]
+            selector = "span"
+            type = "int"
+            "#,
+        );
+        let html = r#"<img src="1.jpg"><img src="2.jpg"><meta content="true">
+            <ul><li class="variant" data-sku="10"><span>1 990</span></li><li class="variant" data-sku="11"></li></ul>"#;
+        let pd = rules.extract("mm", html).unwrap().to_product_data();
+        assert_eq!(pd.images, Some(vec!["1.jpg".to_string(), "2.jpg".to_string()]));
+        assert_eq!(pd.available, Some(true));
+        let variants = pd.variants.unwrap();
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[0].sku.as_deref(), Some("10"));
+        assert_eq!(variants[0].price, Some(1990));
+        assert_eq!(variants[1].price, None);
+    }
+
+    #[test]
+    fn test_detect_rules() {
+        let rules = compile(
+            r#"
+            [ym]
+            format = "html"
+            root = "main"
+            detect.captcha = ["div.CheckboxCaptcha"]
+            detect.not_found = ["div.error-404"]
+            [ym.fields.name]
+            selector = "h1"
+            [wb]
+            format = "json"
+            root = "/data/products/0"
+            detect.blocked = ["/error/blocked"]
+            [wb.fields.name]
+            pointer = "/name"
+            "#,
+        );
+        let extracted = rules
+            .extract("ym", r#"<div class="CheckboxCaptcha"></div>"#)
+            .unwrap();
+        assert_eq!(extracted.status, PageStatus::Captcha);
+        assert!(extracted.values.is_empty());
+        let extracted = rules.extract("ym", "<main><h1>Набор</h1></main>").unwrap();
+        assert_eq!(extracted.status, PageStatus::Ok);
+        let extracted = rules
+            .extract("wb", r#"{"error": {"blocked": true}}"#)
+            .unwrap();
+        assert_eq!(extracted.status, PageStatus::Blocked);
+    }
+
+    #[test]
+    fn test_page_rules() {
+        let rules = compile(
+            r#"
+            [wb]
+            format = "json"
+            [wb.fields.name]
+            pointer = "/name"
+            [wb.pages.characteristics]
+            format = "json"
+            [wb.pages.characteristics.fields.characteristics]
+            pointer = "/groups/[]/options"
+            many = true
+            type = "map"
+            [wb.pages.characteristics.fields.characteristics.fields.key]
+            pointer = "/name"
+            [wb.pages.characteristics.fields.characteristics.fields.value]
+            pointer = "/value"
+            "#,
+        );
+        let json = r#"{"groups": [{"options": [{"name": "Цвет", "value": "черный"}]},
+            {"options": [{"name": "Состав", "value": "хлопок"}, {"name": "Пустое"}]}]}"#;
+        let pd = rules
+            .extract_page("wb", "characteristics", json)
+            .unwrap()
+            .to_product_data();
+        let characteristics = pd.characteristics.unwrap();
+        assert_eq!(characteristics.len(), 2);
+        assert_eq!(characteristics["Цвет"], "черный");
+        assert_eq!(characteristics["Состав"], "хлопок");
+        assert!(rules.extract_page("wb", "reviews", json).is_err());
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let invalid = [
+            "[wb]\nformat = \"json\"\n[wb.fields.price]\npointer = \"/price\"\ntype = \"float\"",
+            "[wb]\nformat = \"json\"\n[wb.fields.name]\nselector = \"h1\"",
+            "[mm]\nformat = \"html\"\n[mm.fields.name]\nselector = \"h1[\"",
+            "[mm]\nformat = \"html\"\n[mm.fields.name]\nselector = \"h1\"\nregex = \"(\"",
+            "[wb]\nformat = \"json\"\n[wb.fields.images]\npointer = \"/images\"\nitem = \"/src\"",
+            "[wb]\nformat = \"json\"\n[wb.fields.images]\npointer = \"/images\"\nmany = true\ntype = \"int\"",
+            "[wb]\nformat = \"json\"\n[wb.fields.characteristics]\npointer = \"/options\"\nmany = true\ntype = \"map\"",
+        ];
+        for content in invalid {
+            assert!(Rules::parse(content, false).unwrap().compile().is_err());
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use browser_bridge::PageStatus;
+use indexmap::IndexMap;
+
+use super::rules::{self, CompiledRules};
+use crate::models::scraper::{SellerData, Symbol};
+
+pub const SELLER_PAGE: &str = "seller";
+
+/// Профиль продавца, извлеченный со страницы продавца
+#[derive(Debug, Default)]
+pub struct Seller {
+    pub status: PageStatus,
+    pub data: Option<SellerData>,
+    /// Поля, которые не удалось извлечь, и причины
+    pub failures: IndexMap<String, String>,
+}
+
+pub fn extract_seller(symbol: Symbol, seller_id: &str, content: &str) -> Result<Seller, String> {
+    extract_seller_with(&rules::get(), symbol, seller_id, content)
+}
+
+pub fn extract_seller_with(
+    rules: &CompiledRules,
+    symbol: Symbol,
+    seller_id: &str,
+    content: &str,
+) -> Result<Seller, String> {
+    let extracted = rules.extract_page(symbol.as_str(), SELLER_PAGE, content)?;
+    let data = (!extracted.values.is_empty()).then(|| SellerData {
+        id: seller_id.into(),
+        url: symbol.market().seller_url(seller_id).unwrap_or_default(),
+        ..extracted.to_data()
+    });
+
+    Ok(Seller {
+        status: extracted.status,
+        data,
+        failures: extracted.failures,
+    })
+}
@@ -0,0 +1,128 @@
+use reqwest::Url;
+
+use super::{
+    super::{extractor::rules::Extracted, req::ReqMethod},
+    is_u64, MarketExtractor,
+};
+use crate::models::scraper::ProductData;
+
+pub struct MegaMarket;
+
+impl MarketExtractor for MegaMarket {
+    fn symbol(&self) -> &'static str {
+        "mm"
+    }
+
+    fn name(&self) -> &'static str {
+        "MegaMarket"
+    }
+
+    fn url(&self) -> &'static str {
+        "https://megamarket.ru/"
+    }
+
+    fn product_url_prefix(&self) -> &'static str {
+        "https://megamarket.ru/catalog/details/"
+    }
+
+    fn id_from_url(&self, url: &Url) -> Option<String> {
+        let segment = url.path_segments()?.nth(2)?;
+        match segment.rsplit_once('-') {
+            Some((_, id)) => Some(id.into()),
+            None => Some(segment.into()),
+        }
+    }
+
+    fn validate_id(&self, id: &str) -> bool {
+        is_u64(id)
+    }
+
+    fn product_url(&self, id: &str) -> String {
+        format!("https://megamarket.ru/promo-page/details/#?slug={}", id)
+    }
+
+    fn parse_url(&self, id: &str) -> String {
+        format!("https://megamarket.ru/promo-page/details/#?slug={}", id)
+    }
+
+    fn search_url(&self, query: &str, page: u64) -> Option<String> {
+        let url = Url::parse_with_params("https://megamarket.ru/catalog/", &[("q", query)]).ok()?;
+        Some(catalog_page_url(&url, page))
+    }
+
+    fn category_url(&self, url: &Url, page: u64) -> Option<String> {
+        let path = url.path();
+        if !path.starts_with("/catalog/") || path.starts_with("/catalog/details/") {
+            return None;
+        }
+        Some(catalog_page_url(url, page))
+    }
+
+    fn seller_url(&self, seller_id: &str) -> Option<String> {
+        let valid = !seller_id.is_empty()
+            && seller_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        valid.then(|| format!("https://megamarket.ru/shop/{}/", seller_id))
+    }
+
+    fn seller_listing_url(&self, seller_id: &str, page: u64) -> Option<String> {
+        let url = Url::parse(&self.seller_url(seller_id)?).ok()?;
+        Some(catalog_page_url(&url, page))
+    }
+
+    fn req_method(&self) -> ReqMethod {
+        ReqMethod::Browser
+    }
+
+    fn finalize(&self, pd: &mut ProductData, extracted: &Extracted) {
+        if let (Some(price), Some(bonus)) = (pd.price, extracted.get_u64("bonus")) {
+            pd.cprice = Some(price.saturating_sub(bonus));
+        }
+    }
+}
+
+/// Ссылка на страницу каталога: номер страницы в пути (`/page-2/`)
+fn catalog_page_url(url: &Url, page: u64) -> String {
+    let mut url = url.clone();
+    let segments = url
+        .path_segments()
+        .map(|segments| {
+            segments
+                .filter(|s| !s.is_empty() && !s.starts_with("page-"))
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .unwrap_or_default();
+    let mut path = format!("/{segments}/");
+    if page > 1 {
+        path.push_str(&format!("page-{page}/"));
+    }
+    url.set_path(&path);
+
+    url.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mm_listing_url() {
+        assert_eq!(
+            MegaMarket.search_url("отвертки", 2).unwrap(),
+            "https://megamarket.ru/catalog/page-2/?q=%D0%BE%D1%82%D0%B2%D0%B5%D1%80%D1%82%D0%BA%D0%B8"
+        );
+        let url = Url::parse("https://megamarket.ru/catalog/otvertki/page-3/").unwrap();
+        assert_eq!(
+            MegaMarket.category_url(&url, 1).unwrap(),
+            "https://megamarket.ru/catalog/otvertki/"
+        );
+        let url = Url::parse("https://megamarket.ru/catalog/details/nabor-100065768898/").unwrap();
+        assert!(MegaMarket.category_url(&url, 1).is_none());
+        assert_eq!(
+            MegaMarket.seller_listing_url("instrument-market", 3).unwrap(),
+            "https://megamarket.ru/shop/instrument-market/page-3/"
+        );
+    }
+}
@@ -0,0 +1,526 @@
+mod mm;
+mod oz;
+mod wb;
+mod ym;
+
+use browser_bridge::{PageParam, PageStatus};
+use indexmap::IndexMap;
+use reqwest::Url;
+use std::sync::LazyLock;
+
+use super::{
+    super::{config as cfg, models::scraper::ProductData},
+    extractor::rules::{CompiledRules, Extracted},
+    req::ReqMethod,
+};
+
+/// Описание маркетплейса: распознавание ссылок, валидация идентификатора,
+/// построение ссылок для парсинга, способ запроса и извлечение данных.
+///
+/// Чтобы добавить маркетплейс, достаточно реализовать трейт в отдельном модуле
+/// и добавить его в [`register`].
+pub trait MarketExtractor: Send + Sync {
+    /// Символ маркетплейса (`oz`, `wb`, ...)
+    fn symbol(&self) -> &'static str;
+
+    fn name(&self) -> &'static str;
+
+    fn url(&self) -> &'static str;
+
+    /// Префикс ссылок на товар, по которому распознается маркетплейс
+    fn product_url_prefix(&self) -> &'static str;
+
+    /// Извлекает идентификатор товара из ссылки маркетплейса
+    fn id_from_url(&self, url: &Url) -> Option<String>;
+
+    fn validate_id(&self, id: &str) -> bool;
+
+    fn sku(&self, id: &str) -> String {
+        id.into()
+    }
+
+    fn product_url(&self, id: &str) -> String;
+
+    fn parse_url(&self, id: &str) -> String;
+
+    /// Ссылка на запрос нескольких товаров одним ответом, если маркетплейс его поддерживает
+    fn batch_url(&self, _ids: &[&str]) -> Option<String> {
+        None
+    }
+
+    /// Делит ответ на запрос по [`MarketExtractor::batch_url`] на ответы по каждому товару
+    /// в порядке `ids`, такие же, как по [`MarketExtractor::parse_url`]
+    fn split_batch(&self, _ids: &[&str], _content: &str) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Ссылка на страницу характеристик, если их нет в основном контенте
+    fn characteristics_url(&self, _id: &str) -> Option<String> {
+        None
+    }
+
+    /// Ссылка на страницу поисковой выдачи, если поиск поддерживается
+    fn search_url(&self, _query: &str, _page: u64) -> Option<String> {
+        None
+    }
+
+    /// Ссылка на страницу выдачи категории по ее ссылке на маркетплейсе,
+    /// если категории поддерживаются
+    fn category_url(&self, _url: &Url, _page: u64) -> Option<String> {
+        None
+    }
+
+    /// Ссылка на витрину продавца, если витрины продавцов поддерживаются
+    fn seller_url(&self, _seller_id: &str) -> Option<String> {
+        None
+    }
+
+    /// Ссылка на страницу товаров продавца
+    fn seller_listing_url(&self, _seller_id: &str, _page: u64) -> Option<String> {
+        None
+    }
+
+    /// Ссылка на страницу с профилем продавца, по умолчанию первая страница его товаров
+    fn seller_profile_url(&self, seller_id: &str) -> Option<String> {
+        self.seller_listing_url(seller_id, 1)
+    }
+
+    /// Ссылка на страницу отзывов о товаре с номером `page` (с 1), если отзывы поддерживаются.
+    /// `content` - основной ответ маркетплейса по товару, если ссылка строится по его данным
+    fn reviews_url(&self, _id: &str, _content: &str, _page: u64) -> Option<String> {
+        None
+    }
+
+    fn req_method(&self) -> ReqMethod;
+
+    /// Нужно ли перед обработкой заказа сделать прогревочный запрос (cookies)
+    fn warm_up(&self) -> bool {
+        false
+    }
+
+    fn page_param(&self) -> PageParam<'static> {
+        let cfg_page_param = &cfg::get().browser.page_param;
+        let wait_el_timeout = cfg_page_param.wait_for_el_timeout;
+        let symbol_param = cfg_page_param.symbol.get(self.symbol());
+        PageParam {
+            wait_for_el: symbol_param.and_then(|v| {
+                v.wait_for_el
+                    .as_ref()
+                    .map(|v| (v.as_str(), wait_el_timeout))
+            }),
+            wait_for_el_until: symbol_param.and_then(|v| {
+                v.wait_for_el_until
+                    .as_ref()
+                    .map(|v| (v.0.as_str(), v.1.as_str(), wait_el_timeout))
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Извлекает данные о товаре по правилам маркетплейса из файла правил
+    fn extract(&self, rules: &CompiledRules, content: &str) -> Extraction {
+        match rules.extract(self.symbol(), content) {
+            Ok(extracted) => {
+                let mut pd = extracted.to_product_data();
+                self.finalize(&mut pd, &extracted);
+                pd.fill_derived();
+                Extraction {
+                    status: extracted.status,
+                    data: pd.to_option(),
+                    error: None,
+                    failures: extracted.failures,
+                }
+            }
+            Err(e) => Extraction {
+                error: Some(e),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Расчет производных полей, которые нельзя описать правилами
+    fn finalize(&self, _pd: &mut ProductData, _extracted: &Extracted) {}
+}
+
+/// Результат извлечения данных о товаре
+#[derive(Debug, Default)]
+pub struct Extraction {
+    /// Классификация контента по признакам из файла правил
+    pub status: PageStatus,
+    pub data: Option<ProductData>,
+    /// Причина, по которой контент не удалось разобрать целиком
+    pub error: Option<String>,
+    /// Поля, которые не удалось извлечь, и причины
+    pub failures: IndexMap<String, String>,
+}
+
+fn register() -> Vec<Box<dyn MarketExtractor>> {
+    vec![
+        Box::new(oz::Ozon),
+        Box::new(wb::Wildberries),
+        Box::new(ym::YandexMarket),
+        Box::new(mm::MegaMarket),
+    ]
+}
+
+static REGISTRY: LazyLock<IndexMap<&'static str, Box<dyn MarketExtractor>>> =
+    LazyLock::new(|| register().into_iter().map(|m| (m.symbol(), m)).collect());
+
+pub fn get(symbol: &str) -> Option<&'static dyn MarketExtractor> {
+    REGISTRY
+        .get(symbol.to_lowercase().as_str())
+        .map(|m| m.as_ref())
+}
+
+pub fn all() -> impl Iterator<Item = &'static dyn MarketExtractor> {
+    REGISTRY.values().map(|m| m.as_ref())
+}
+
+pub fn from_product_url(s: &str) -> Option<&'static dyn MarketExtractor> {
+    all().find(|m| s.starts_with(m.product_url_prefix()))
+}
+
+/// Маркетплейс по домену ссылки
+pub fn from_url_host(url: &Url) -> Option<&'static dyn MarketExtractor> {
+    let host = url.host_str()?.trim_start_matches("www.");
+    all().find(|m| {
+        Url::parse(m.url())
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.trim_start_matches("www.") == host))
+            .unwrap_or(false)
+    })
+}
+
+/// Ссылка с номером страницы выдачи в параметре `name`. Первая страница без параметра.
+fn with_page_param(url: &Url, name: &str, page: u64) -> Url {
+    let mut url = url.clone();
+    let pairs = url
+        .query_pairs()
+        .filter(|(k, _)| k != name)
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect::<Vec<_>>();
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+    if page > 1 {
+        url.query_pairs_mut().append_pair(name, &page.to_string());
+    }
+    if url.query() == Some("") {
+        url.set_query(None);
+    }
+
+    url
+}
+
+#[inline]
+fn is_u64(s: &str) -> bool {
+    s.parse::<u64>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::scraper::{Product, Symbol},
+        scraper::{
+            extractor::{
+                listing::{extract_listing_with, LISTING_PAGE},
+                product::{extract_characteristics_with, extract_data_with, CHARACTERISTICS_PAGE},
+                reviews::{extract_reviews_with, REVIEWS_PAGE},
+                seller::{extract_seller_with, SELLER_PAGE},
+                rules::{ContentFormat, Rules},
+            },
+            req::ReqSession,
+        },
+    };
+    use serde_json::{json, Value};
+    use std::{
+        collections::{BTreeMap, HashSet},
+        fs,
+        path::Path,
+    };
+
+    /// Сохраненные ответы маркетплейсов
+    const FIXTURES_DIR: &str = "fixtures";
+    /// Образцы разметки для правил, не ответы маркетплейсов
+    const SAMPLES_DIR: &str = "fixtures/samples";
+
+    fn load_rules() -> CompiledRules {
+        let content = fs::read_to_string("Rules.toml").unwrap();
+        Rules::parse(&content, false).unwrap().compile().unwrap()
+    }
+
+    /// Результат извлечения для эталона. Характеристики берутся из
+    /// `characteristics/<id>.(html|json)`, если он есть, иначе из основного контента.
+    fn fixture_result(
+        rules: &CompiledRules,
+        product: &Product,
+        content: &str,
+        characteristics: Option<&str>,
+    ) -> Value {
+        let mut extraction = extract_data_with(rules, product, content);
+        if let Some(pd) = extraction.data.as_mut() {
+            pd.characteristics =
+                extract_characteristics_with(rules, product, characteristics.unwrap_or(content))
+                    .and_then(|c| c.values())
+                    .ok();
+        }
+        json!({ "status": extraction.status, "data": extraction.data })
+    }
+
+    fn read_page(dir: &Path, id: &str, page: &str) -> Option<String> {
+        ["html", "json"]
+            .iter()
+            .find_map(|ext| fs::read_to_string(dir.join(page).join(format!("{id}.{ext}"))).ok())
+    }
+
+    fn write_json(path: &Path, value: &Value) {
+        let content = serde_json::to_string_pretty(value).unwrap();
+        fs::write(path, content + "\n").unwrap();
+    }
+
+    #[test]
+    fn test_registry() {
+        let symbols = all().map(|m| m.symbol()).collect::<Vec<_>>();
+        assert_eq!(symbols, vec!["oz", "wb", "ym", "mm"]);
+        assert!(get("WB").is_some());
+        assert!(get("rt").is_none());
+    }
+
+    /// Сверяет результаты извлечения для ответов из `dir` с ожидаемыми `<name>.expected.json`.
+    /// С `BLESS=1` ожидаемые результаты перезаписываются текущими.
+    /// Возвращает количество проверенных ответов.
+    fn check_fixtures(
+        dir: &Path,
+        mismatches: &mut Vec<String>,
+        result: impl Fn(&str, &str) -> Value,
+    ) -> usize {
+        let bless = std::env::var("BLESS").is_ok_and(|v| v == "1");
+        let mut paths = fs::read_dir(dir)
+            .map(|entries| entries.map(|e| e.unwrap().path()).collect::<Vec<_>>())
+            .unwrap_or_default();
+        paths.sort();
+        let mut count = 0;
+        for path in paths {
+            let file_name = path.file_name().unwrap().to_str().unwrap();
+            let Some((name, "html" | "json")) = file_name.rsplit_once('.') else {
+                continue;
+            };
+            if name.contains('.') {
+                continue;
+            }
+            let content = fs::read_to_string(&path).unwrap();
+            let actual = result(name, &content);
+            let expected_path = dir.join(format!("{name}.expected.json"));
+            if bless {
+                write_json(&expected_path, &actual);
+            } else {
+                let expected = fs::read_to_string(&expected_path)
+                    .ok()
+                    .and_then(|s| serde_json::from_str::<Value>(&s).ok());
+                if expected.as_ref() != Some(&actual) {
+                    mismatches.push(format!(
+                        "{}\n  expected: {}\n  actual:   {actual}",
+                        path.display(),
+                        expected.unwrap_or_default()
+                    ));
+                }
+            }
+            count += 1;
+        }
+
+        count
+    }
+
+    /// Регрессионные тесты извлечения на сохраненных ответах маркетплейсов и образцах разметки.
+    /// `<dir>/<symbol>/<id>.(html|json)` - ответ, `<id>.expected.json` - ожидаемый результат.
+    /// `<id>@<метка>` - другой ответ для того же товара.
+    #[test]
+    fn test_fixtures() {
+        let rules = load_rules();
+        let mut counts = BTreeMap::new();
+        let mut mismatches = Vec::new();
+        for dir in [FIXTURES_DIR, SAMPLES_DIR] {
+            for market in all() {
+                let dir = Path::new(dir).join(market.symbol());
+                let count = check_fixtures(&dir, &mut mismatches, |name, content| {
+                    let id = name.split_once('@').map_or(name, |(id, _)| id);
+                    let product =
+                        Product::from_string_without_valid(&format!("{}/{id}", market.symbol()));
+                    let characteristics = read_page(&dir, name, CHARACTERISTICS_PAGE);
+                    fixture_result(&rules, &product, content, characteristics.as_deref())
+                });
+                counts.insert(dir, count);
+            }
+        }
+        println!("fixtures: {counts:?}");
+
+        assert!(
+            mismatches.is_empty(),
+            "fixtures mismatch (BLESS=1 to accept):\n{}",
+            mismatches.join("\n")
+        );
+        for market in all() {
+            let samples = Path::new(SAMPLES_DIR).join(market.symbol());
+            assert!(counts[&samples] > 0, "no samples for '{}'", market.symbol());
+            let captured = Path::new(FIXTURES_DIR).join(market.symbol());
+            if counts[&captured] == 0 {
+                println!("no captured fixtures for '{}'", market.symbol());
+            }
+        }
+    }
+
+    /// Регрессионные тесты извлечения с дополнительных страниц:
+    /// `<dir>/<symbol>/<page>/<name>.(html|json)`. Эталоны обязательны для маркетплейсов,
+    /// у которых есть правила страницы.
+    fn check_page_fixtures(
+        rules: &CompiledRules,
+        page: &str,
+        result: impl Fn(Symbol, &str, &str) -> Value,
+    ) {
+        let mut counts = BTreeMap::new();
+        let mut mismatches = Vec::new();
+        let with_page = |symbol: &str| {
+            rules
+                .source
+                .0
+                .get(symbol)
+                .is_some_and(|m| m.pages.contains_key(page))
+        };
+        for market in all().filter(|m| with_page(m.symbol())) {
+            let symbol = Symbol::from_string(market.symbol()).unwrap();
+            let count = [FIXTURES_DIR, SAMPLES_DIR]
+                .iter()
+                .map(|dir| {
+                    let dir = Path::new(dir).join(market.symbol()).join(page);
+                    check_fixtures(&dir, &mut mismatches, |name, content| {
+                        result(symbol, name, content)
+                    })
+                })
+                .sum::<usize>();
+            counts.insert(market.symbol(), count);
+        }
+
+        assert!(
+            mismatches.is_empty(),
+            "{page} fixtures mismatch (BLESS=1 to accept):\n{}",
+            mismatches.join("\n")
+        );
+        for (symbol, count) in counts {
+            assert!(count > 0, "no {page} fixtures for '{symbol}'");
+        }
+    }
+
+    #[test]
+    fn test_listing_fixtures() {
+        let rules = load_rules();
+        check_page_fixtures(&rules, LISTING_PAGE, |symbol, _, content| {
+            match extract_listing_with(&rules, symbol, content) {
+                Ok(listing) => json!({ "status": listing.status, "products": listing.products }),
+                Err(e) => json!({ "error": e }),
+            }
+        });
+    }
+
+    /// Имя файла - идентификатор продавца
+    #[test]
+    fn test_seller_fixtures() {
+        let rules = load_rules();
+        check_page_fixtures(&rules, SELLER_PAGE, |symbol, seller_id, content| {
+            match extract_seller_with(&rules, symbol, seller_id, content) {
+                Ok(seller) => json!({ "status": seller.status, "data": seller.data }),
+                Err(e) => json!({ "error": e }),
+            }
+        });
+    }
+
+    /// Имя файла - идентификатор товара
+    #[test]
+    fn test_reviews_fixtures() {
+        let rules = load_rules();
+        check_page_fixtures(&rules, REVIEWS_PAGE, |symbol, id, content| {
+            let product = Product::from_string_without_valid(&format!("{}/{id}", symbol.as_str()));
+            match extract_reviews_with(&rules, &product, content) {
+                Ok(reviews) => json!({ "status": reviews.status, "reviews": reviews.reviews }),
+                Err(e) => json!({ "error": e }),
+            }
+        });
+    }
+
+    /// Сохраняет ответы маркетплейсов для товаров из `FIXTURES_CAPTURE`
+    /// (`oz/1680678914,wb/259666228`) и ожидаемые результаты для них.
+    /// `cargo test capture_fixtures -- --ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn capture_fixtures() {
+        cfg::init();
+        let rules = load_rules();
+        let products = std::env::var("FIXTURES_CAPTURE").unwrap_or_default();
+        let mut req_session = ReqSession::new(
+            &cfg::get().req_session,
+            ReqMethod::Combined,
+            &vec![],
+            vec![],
+            Default::default(),
+        )
+        .await
+        .unwrap();
+        let mut warmed_up = HashSet::new();
+        for s in products.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let product = Product::from_string_without_valid(s);
+            let market = product.symbol.market();
+            if market.warm_up() && warmed_up.insert(product.symbol) {
+                let _ = req_session.fetch_product_content(&product).await;
+            }
+            let fetched = match req_session.fetch_product_content(&product).await {
+                Ok(fetched) => fetched,
+                Err(e) => {
+                    println!("{s}: {e}");
+                    continue;
+                }
+            };
+            let ext = match rules.source.0.get(market.symbol()).map(|r| r.format) {
+                Some(ContentFormat::Json) => "json",
+                _ => "html",
+            };
+            let dir = Path::new(FIXTURES_DIR).join(market.symbol());
+            fs::create_dir_all(&dir).unwrap();
+            // Сохраненные ответы не перезаписываются: повторный ответ - новый эталон `<id>@<дата>`
+            let mut name = product.id.to_string();
+            if dir.join(format!("{name}.{ext}")).exists() {
+                name = format!("{name}@{}", chrono::Utc::now().format("%Y-%m-%d"));
+            }
+            if dir.join(format!("{name}.{ext}")).exists() {
+                println!("{s}: {name}.{ext} already exists");
+                continue;
+            }
+            fs::write(dir.join(format!("{name}.{ext}")), &fetched.content).unwrap();
+            let result = fixture_result(&rules, &product, &fetched.content, None);
+            write_json(&dir.join(format!("{name}.expected.json")), &result);
+            println!("{s}: {name} {}", result["status"]);
+        }
+
+        req_session.close().await;
+    }
+
+    #[test]
+    fn test_with_page_param() {
+        let url = Url::parse("https://market.yandex.ru/search?text=ручка&page=3").unwrap();
+        assert_eq!(
+            with_page_param(&url, "page", 2).as_str(),
+            "https://market.yandex.ru/search?text=%D1%80%D1%83%D1%87%D0%BA%D0%B0&page=2"
+        );
+        let url = Url::parse("https://market.yandex.ru/catalog--ruchki/123/list?page=3").unwrap();
+        assert_eq!(
+            with_page_param(&url, "page", 1).as_str(),
+            "https://market.yandex.ru/catalog--ruchki/123/list"
+        );
+        let url = Url::parse("https://ozon.ru/category/ruchki-123/").unwrap();
+        assert_eq!(from_url_host(&url).map(|m| m.symbol()), Some("oz"));
+    }
+
+    #[test]
+    fn test_from_product_url() {
+        let market = from_product_url("https://www.wildberries.ru/catalog/95979396/detail.aspx");
+        assert_eq!(market.map(|m| m.symbol()), Some("wb"));
+        assert!(from_product_url("https://example.com/product/1").is_none());
+    }
+}
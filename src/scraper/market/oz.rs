@@ -0,0 +1,149 @@
+use reqwest::Url;
+
+use super::{super::req::ReqMethod, is_u64, with_page_param, MarketExtractor};
+
+pub struct Ozon;
+
+impl MarketExtractor for Ozon {
+    fn symbol(&self) -> &'static str {
+        "oz"
+    }
+
+    fn name(&self) -> &'static str {
+        "Ozon"
+    }
+
+    fn url(&self) -> &'static str {
+        "https://ozon.ru"
+    }
+
+    fn product_url_prefix(&self) -> &'static str {
+        "https://www.ozon.ru/product/"
+    }
+
+    fn id_from_url(&self, url: &Url) -> Option<String> {
+        let segment = url.path_segments()?.nth(1)?;
+        match segment.rsplit_once('-') {
+            Some((_, id)) => Some(id.into()),
+            None => Some(segment.into()),
+        }
+    }
+
+    fn validate_id(&self, id: &str) -> bool {
+        is_u64(id)
+    }
+
+    fn product_url(&self, id: &str) -> String {
+        format!("https://www.ozon.ru/product/{}", id)
+    }
+
+    fn parse_url(&self, id: &str) -> String {
+        format!(
+            "https://www.ozon.ru/api/entrypoint-api.bx/page/json/v2?url=/product/{}/",
+            id
+        )
+    }
+
+    fn search_url(&self, query: &str, page: u64) -> Option<String> {
+        let url = Url::parse_with_params("https://www.ozon.ru/search/", &[("text", query)]).ok()?;
+        page_json_url(&url, page)
+    }
+
+    fn category_url(&self, url: &Url, page: u64) -> Option<String> {
+        if !url.path().starts_with("/category/") {
+            return None;
+        }
+        page_json_url(url, page)
+    }
+
+    fn seller_url(&self, seller_id: &str) -> Option<String> {
+        validate_seller_id(seller_id).then(|| format!("https://www.ozon.ru/seller/{}/", seller_id))
+    }
+
+    fn seller_listing_url(&self, seller_id: &str, page: u64) -> Option<String> {
+        let url = Url::parse(&self.seller_url(seller_id)?).ok()?;
+        page_json_url(&url, page)
+    }
+
+    fn reviews_url(&self, id: &str, _content: &str, page: u64) -> Option<String> {
+        let url = Url::parse(&format!("https://www.ozon.ru/product/{}/reviews/", id)).ok()?;
+        page_json_url(&url, page)
+    }
+
+    fn req_method(&self) -> ReqMethod {
+        ReqMethod::Browser
+    }
+
+    fn characteristics_url(&self, id: &str) -> Option<String> {
+        Some(format!(
+            "https://www.ozon.ru/api/entrypoint-api.bx/page/json/v2?url=/product/{}/?layout_container=pdpPage2column&layout_page_index=2",
+            id
+        ))
+    }
+
+    fn warm_up(&self) -> bool {
+        true
+    }
+}
+
+/// Идентификатор продавца из ссылки на витрину: `ofismag-172339` или `172339`
+fn validate_seller_id(seller_id: &str) -> bool {
+    match seller_id.rsplit_once('-') {
+        Some((slug, id)) => {
+            is_u64(id) && slug.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        }
+        None => is_u64(seller_id),
+    }
+}
+
+/// Ссылка на JSON страницы выдачи через entrypoint-api
+fn page_json_url(url: &Url, page: u64) -> Option<String> {
+    let url = with_page_param(url, "page", page);
+    let path = match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().into(),
+    };
+    Url::parse_with_params(
+        "https://www.ozon.ru/api/entrypoint-api.bx/page/json/v2",
+        &[("url", path)],
+    )
+    .ok()
+    .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oz_id_from_url() {
+        let url =
+            Url::parse("https://www.ozon.ru/product/nozhnitsy-kantselyarskie-21-sm-173091046/")
+                .unwrap();
+        assert_eq!(Ozon.id_from_url(&url), Some("173091046".into()));
+    }
+
+    #[test]
+    fn test_oz_listing_url() {
+        assert_eq!(
+            Ozon.search_url("ножницы", 2).unwrap(),
+            "https://www.ozon.ru/api/entrypoint-api.bx/page/json/v2?url=%2Fsearch%2F%3Ftext%3D%25D0%25BD%25D0%25BE%25D0%25B6%25D0%25BD%25D0%25B8%25D1%2586%25D1%258B%26page%3D2"
+        );
+        let url = Url::parse("https://www.ozon.ru/category/nozhnitsy-15696/?page=4").unwrap();
+        assert_eq!(
+            Ozon.category_url(&url, 1).unwrap(),
+            "https://www.ozon.ru/api/entrypoint-api.bx/page/json/v2?url=%2Fcategory%2Fnozhnitsy-15696%2F"
+        );
+        let url = Url::parse("https://www.ozon.ru/seller/ofismag-172339/").unwrap();
+        assert!(Ozon.category_url(&url, 1).is_none());
+        assert_eq!(
+            Ozon.seller_listing_url("ofismag-172339", 2).unwrap(),
+            "https://www.ozon.ru/api/entrypoint-api.bx/page/json/v2?url=%2Fseller%2Fofismag-172339%2F%3Fpage%3D2"
+        );
+        assert!(Ozon.seller_url("ofismag/172339").is_none());
+        assert_eq!(
+            Ozon.reviews_url("173091046", "", 3).unwrap(),
+            "https://www.ozon.ru/api/entrypoint-api.bx/page/json/v2?url=%2Fproduct%2F173091046%2Freviews%2F%3Fpage%3D3"
+        );
+    }
+}
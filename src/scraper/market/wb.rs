@@ -0,0 +1,195 @@
+use reqwest::Url;
+use serde_json::{json, Value};
+
+use super::{
+    super::{extractor::rules::Extracted, req::ReqMethod},
+    is_u64, MarketExtractor,
+};
+use crate::models::scraper::ProductData;
+
+pub struct Wildberries;
+
+impl MarketExtractor for Wildberries {
+    fn symbol(&self) -> &'static str {
+        "wb"
+    }
+
+    fn name(&self) -> &'static str {
+        "Wildberries"
+    }
+
+    fn url(&self) -> &'static str {
+        "https://www.wildberries.ru/"
+    }
+
+    fn product_url_prefix(&self) -> &'static str {
+        "https://www.wildberries.ru/catalog/"
+    }
+
+    fn id_from_url(&self, url: &Url) -> Option<String> {
+        url.path_segments()?.nth(1).map(String::from)
+    }
+
+    fn validate_id(&self, id: &str) -> bool {
+        is_u64(id)
+    }
+
+    fn product_url(&self, id: &str) -> String {
+        format!("https://www.wildberries.ru/catalog/{}/detail.aspx", id)
+    }
+
+    fn parse_url(&self, id: &str) -> String {
+        format!(
+            "https://card.wb.ru/cards/v2/detail?appType=1&curr=rub&dest=-1257218&nm={}",
+            id
+        )
+    }
+
+    fn batch_url(&self, ids: &[&str]) -> Option<String> {
+        Some(self.parse_url(&ids.join(";")))
+    }
+
+    /// Ответ по каждому товару - ответ с одним товаром (без товара, если его нет в ответе)
+    fn split_batch(&self, ids: &[&str], content: &str) -> Option<Vec<String>> {
+        let value = serde_json::from_str::<Value>(content).ok()?;
+        let products = value.pointer("/data/products")?.as_array()?;
+        let contents = ids
+            .iter()
+            .map(|id| {
+                let product = products
+                    .iter()
+                    .filter(|p| p["id"].as_u64().is_some_and(|nm| nm.to_string() == *id))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                json!({ "state": value["state"], "data": { "products": product } }).to_string()
+            })
+            .collect();
+
+        Some(contents)
+    }
+
+    fn characteristics_url(&self, id: &str) -> Option<String> {
+        let nm = id.parse::<u64>().ok()?;
+        let (vol, part) = (nm / 100_000, nm / 1_000);
+        Some(format!(
+            "https://basket-{:02}.wbbasket.ru/vol{vol}/part{part}/{nm}/info/ru/card.json",
+            basket_number(vol)
+        ))
+    }
+
+    fn search_url(&self, query: &str, page: u64) -> Option<String> {
+        Url::parse_with_params(
+            "https://search.wb.ru/exactmatch/ru/common/v5/search?appType=1&curr=rub&dest=-1257218&resultset=catalog&sort=popular&spp=30",
+            &[("query", query), ("page", &page.to_string())],
+        )
+        .ok()
+        .map(String::from)
+    }
+
+    fn seller_url(&self, seller_id: &str) -> Option<String> {
+        is_u64(seller_id).then(|| format!("https://www.wildberries.ru/seller/{}", seller_id))
+    }
+
+    fn seller_listing_url(&self, seller_id: &str, page: u64) -> Option<String> {
+        if !is_u64(seller_id) {
+            return None;
+        }
+        Some(format!(
+            "https://catalog.wb.ru/sellers/v2/catalog?appType=1&curr=rub&dest=-1257218&sort=popular&spp=30&supplier={}&page={}",
+            seller_id, page
+        ))
+    }
+
+    fn seller_profile_url(&self, seller_id: &str) -> Option<String> {
+        is_u64(seller_id).then(|| {
+            format!(
+                "https://static-basket-01.wbbasket.ru/vol0/data/supplier-by-id/{}.json",
+                seller_id
+            )
+        })
+    }
+
+    /// Отзывы общие для карточки (`root`) и отдаются одним ответом без страниц
+    fn reviews_url(&self, _id: &str, content: &str, page: u64) -> Option<String> {
+        if page > 1 {
+            return None;
+        }
+        let root = serde_json::from_str::<serde_json::Value>(content)
+            .ok()?
+            .pointer("/data/products/0/root")?
+            .as_u64()?;
+        Some(format!("https://feedbacks1.wb.ru/feedbacks/v1/{}", root))
+    }
+
+    fn req_method(&self) -> ReqMethod {
+        ReqMethod::Reqwest
+    }
+
+    fn finalize(&self, pd: &mut ProductData, _extracted: &Extracted) {
+        if let Some(price) = pd.price {
+            pd.cprice = Some((price as f64 * 0.98) as u64);
+        }
+    }
+}
+
+/// Номер хоста статики (basket) по `vol = nm / 100000`
+fn basket_number(vol: u64) -> u64 {
+    const UPPER_VOLS: [u64; 17] = [
+        143, 287, 431, 719, 1007, 1061, 1115, 1169, 1313, 1601, 1655, 1919, 2045, 2189, 2405,
+        2621, 2837,
+    ];
+    UPPER_VOLS
+        .iter()
+        .position(|upper| vol <= *upper)
+        .unwrap_or(UPPER_VOLS.len()) as u64
+        + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wb_characteristics_url() {
+        assert_eq!(
+            Wildberries.characteristics_url("95979396").as_deref(),
+            Some("https://basket-05.wbbasket.ru/vol959/part95979/95979396/info/ru/card.json")
+        );
+        assert_eq!(basket_number(0), 1);
+        assert_eq!(basket_number(3000), 18);
+    }
+
+    #[test]
+    fn test_wb_batch() {
+        assert_eq!(
+            Wildberries.batch_url(&["248939630", "27090074"]).as_deref(),
+            Some("https://card.wb.ru/cards/v2/detail?appType=1&curr=rub&dest=-1257218&nm=248939630;27090074")
+        );
+        let content = r#"{"state": 0, "data": {"products": [
+            {"id": 27090074, "name": "B"}, {"id": 248939630, "name": "A"}
+        ]}}"#;
+        let contents = Wildberries
+            .split_batch(&["248939630", "1", "27090074"], content)
+            .unwrap();
+        let names = contents
+            .iter()
+            .map(|c| {
+                let value = serde_json::from_str::<Value>(c).unwrap();
+                value.pointer("/data/products/0/name").cloned()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, [Some(json!("A")), None, Some(json!("B"))]);
+        assert!(Wildberries.split_batch(&["1"], "<html>").is_none());
+    }
+
+    #[test]
+    fn test_wb_reviews_url() {
+        let content = r#"{"data": {"products": [{"id": 248939630, "root": 225513472}]}}"#;
+        assert_eq!(
+            Wildberries.reviews_url("248939630", content, 1).as_deref(),
+            Some("https://feedbacks1.wb.ru/feedbacks/v1/225513472")
+        );
+        assert!(Wildberries.reviews_url("248939630", content, 2).is_none());
+        assert!(Wildberries.reviews_url("248939630", "{}", 1).is_none());
+    }
+}
@@ -0,0 +1,129 @@
+use reqwest::Url;
+use std::collections::HashMap;
+
+use super::{super::req::ReqMethod, is_u64, with_page_param, MarketExtractor};
+
+pub struct YandexMarket;
+
+impl MarketExtractor for YandexMarket {
+    fn symbol(&self) -> &'static str {
+        "ym"
+    }
+
+    fn name(&self) -> &'static str {
+        "YandexMarket"
+    }
+
+    fn url(&self) -> &'static str {
+        "https://market.yandex.ru/"
+    }
+
+    fn product_url_prefix(&self) -> &'static str {
+        "https://market.yandex.ru/product"
+    }
+
+    fn id_from_url(&self, url: &Url) -> Option<String> {
+        let segment = url.path_segments()?.nth(1)?;
+        let params = url.query_pairs().collect::<HashMap<_, _>>();
+        Some(format!(
+            "{}-{}-{}",
+            segment,
+            params.get("sku")?,
+            params.get("uniqueId")?,
+        ))
+    }
+
+    fn validate_id(&self, id: &str) -> bool {
+        let parts = id.splitn(3, '-').collect::<Vec<_>>();
+        parts.len() == 3 && parts.iter().all(|v| is_u64(v))
+    }
+
+    fn sku(&self, id: &str) -> String {
+        id.split('-').nth(1).unwrap_or_default().into()
+    }
+
+    fn product_url(&self, id: &str) -> String {
+        let parts = id.splitn(3, '-').collect::<Vec<_>>();
+        format!(
+            "https://market.yandex.ru/product/{}?sku={}&uniqueId={}",
+            parts[0], parts[1], parts[2],
+        )
+    }
+
+    fn parse_url(&self, id: &str) -> String {
+        self.product_url(id)
+    }
+
+    fn search_url(&self, query: &str, page: u64) -> Option<String> {
+        let url = Url::parse_with_params("https://market.yandex.ru/search", &[("text", query)]).ok()?;
+        Some(with_page_param(&url, "page", page).into())
+    }
+
+    fn category_url(&self, url: &Url, page: u64) -> Option<String> {
+        if !url.path().starts_with("/catalog") {
+            return None;
+        }
+        Some(with_page_param(url, "page", page).into())
+    }
+
+    fn seller_url(&self, seller_id: &str) -> Option<String> {
+        is_u64(seller_id).then(|| format!("https://market.yandex.ru/business--shop/{}", seller_id))
+    }
+
+    fn seller_listing_url(&self, seller_id: &str, page: u64) -> Option<String> {
+        let url = Url::parse(&self.seller_url(seller_id)?).ok()?;
+        Some(with_page_param(&url, "page", page).into())
+    }
+
+    fn reviews_url(&self, id: &str, _content: &str, page: u64) -> Option<String> {
+        let parts = id.splitn(3, '-').collect::<Vec<_>>();
+        let url = Url::parse_with_params(
+            &format!("https://market.yandex.ru/product/{}/reviews", parts.first()?),
+            &[("sku", *parts.get(1)?), ("uniqueId", *parts.get(2)?)],
+        )
+        .ok()?;
+        Some(with_page_param(&url, "page", page).into())
+    }
+
+    fn req_method(&self) -> ReqMethod {
+        ReqMethod::Browser
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ym_id() {
+        assert!(YandexMarket.validate_id("1732949807-100352880819-5997015"));
+        assert!(!YandexMarket.validate_id("1732949807-100352880819"));
+        assert_eq!(
+            YandexMarket.sku("1732949807-100352880819-5997015"),
+            "100352880819"
+        );
+    }
+
+    #[test]
+    fn test_ym_listing_url() {
+        assert_eq!(
+            YandexMarket.search_url("ручка", 1).unwrap(),
+            "https://market.yandex.ru/search?text=%D1%80%D1%83%D1%87%D0%BA%D0%B0"
+        );
+        let url = Url::parse("https://market.yandex.ru/catalog--ruchki/18034185/list?hid=1").unwrap();
+        assert_eq!(
+            YandexMarket.category_url(&url, 3).unwrap(),
+            "https://market.yandex.ru/catalog--ruchki/18034185/list?hid=1&page=3"
+        );
+    }
+
+    #[test]
+    fn test_ym_reviews_url() {
+        assert_eq!(
+            YandexMarket
+                .reviews_url("1915673993-102282726841-62878861", "", 2)
+                .unwrap(),
+            "https://market.yandex.ru/product/1915673993/reviews?sku=102282726841&uniqueId=62878861&page=2"
+        );
+    }
+}
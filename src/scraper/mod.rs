@@ -1,4 +1,7 @@
 pub mod error;
 pub mod extractor;
+pub mod limiter;
+pub mod market;
+pub mod proxy;
 pub mod stream;
 pub mod req;
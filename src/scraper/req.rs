@@ -5,30 +5,45 @@ use browser_bridge::{
         },
         Page,
     },
-    random_user_agent, BrowserError, BrowserSession, BrowserSessionConfig, PageParam,
+    random_user_agent, BrowserError, BrowserSession, BrowserSessionConfig, MyIP, PageParam,
+    PageStatus,
 };
+use indexmap::IndexMap;
 use reqwest::cookie::Jar;
 use std::{
     collections::HashMap,
-    sync::{Arc, LazyLock, OnceLock},
-    time::Duration,
+    sync::{Arc, LazyLock, MutexGuard, OnceLock},
+    time::{Duration, Instant},
 };
 use tokio::{sync::Mutex, time::sleep};
 
 use super::{
     super::{
-        config::{self as cfg, ReqSession as ReqSessionConfig},
+        config::{self as cfg, ProxyRotation, ReqSession as ReqSessionConfig, RetryPolicy},
         models::{
-            api::OrderCookieParam,
-            scraper::{Product, ProductData, Symbol},
+            api::{OrderCookieParam, OrderReviews},
+            scraper::{
+                FetchInfo, Product, ProductData, ProductDiagnostics, ProxyStats, ReviewData, SellerData,
+                Symbol,
+            },
             validation::ProxyParam,
         },
         utils::is_port_open,
     },
     error::ReqSessionError,
-    extractor::product::extract_data,
+    extractor::{
+        listing::{extract_listing, Listing},
+        product::{extract_characteristics, extract_data},
+        reviews::extract_reviews,
+        seller::{extract_seller, Seller},
+    },
+    limiter, market,
+    proxy::{ProxyHealth, ProxyRotator},
 };
 
+/// Максимальное количество страниц отзывов на товар
+const REVIEWS_PAGES_LIMIT: u64 = 50;
+
 #[derive(Clone, Debug)]
 struct BrowserState {
     port: u16,
@@ -59,7 +74,12 @@ impl BrowserStates {
             };
         let session = BrowserSession::launch(config).await?;
 
-        Ok(Browser { port, session })
+        Ok(Browser {
+            port,
+            session,
+            proxy: Default::default(),
+            released: false,
+        })
     }
 
     async fn stop_running(&self, port: u16) {
@@ -101,51 +121,13 @@ fn get_browser_states() -> &'static BrowserStates {
 static DEFAULT_PAGE_PARAM: LazyLock<PageParam<'static>> = LazyLock::new(|| PageParam::default());
 static PRODUCT_PAGE_PARAMS: OnceLock<HashMap<String, PageParam>> = OnceLock::new();
 
-pub fn get_product_page_param(symbol: &str) -> &'static PageParam {
+pub fn get_product_page_param(symbol: &str) -> &'static PageParam<'static> {
     PRODUCT_PAGE_PARAMS
         .get_or_init(|| {
-            let cfg_page_param = &cfg::get().browser.page_param;
-            let wait_el_timeout = cfg_page_param.wait_for_el_timeout;
-            let wait_for_el = |s: &str| {
-                cfg_page_param.symbol.get(s).and_then(|v| {
-                    v.wait_for_el
-                        .as_ref()
-                        .map(|v| (v.as_str(), wait_el_timeout))
-                })
-            };
-            let wait_for_el_until = |s: &str| {
-                cfg_page_param.symbol.get(s).and_then(|v| {
-                    v.wait_for_el_until
-                        .as_ref()
-                        .map(|v| (v.0.as_str(), v.1.as_str(), wait_el_timeout))
-                })
-            };
-            HashMap::from([
-                (
-                    Symbol::OZ.as_str().into(),
-                    PageParam {
-                        wait_for_el: wait_for_el(Symbol::OZ.as_str()),
-                        wait_for_el_until: wait_for_el_until(Symbol::OZ.as_str()),
-                        ..Default::default()
-                    },
-                ),
-                (
-                    Symbol::YM.as_str().into(),
-                    PageParam {
-                        wait_for_el: wait_for_el(Symbol::YM.as_str()),
-                        wait_for_el_until: wait_for_el_until(Symbol::YM.as_str()),
-                        ..Default::default()
-                    },
-                ),
-                (
-                    Symbol::MM.as_str().into(),
-                    PageParam {
-                        wait_for_el: wait_for_el(Symbol::MM.as_str()),
-                        wait_for_el_until: wait_for_el_until(Symbol::MM.as_str()),
-                        ..Default::default()
-                    },
-                ),
-            ])
+            market::all()
+                .filter(|m| matches!(m.req_method(), ReqMethod::Browser))
+                .map(|m| (m.symbol().into(), m.page_param()))
+                .collect()
         })
         .get(symbol)
         .unwrap_or(&*DEFAULT_PAGE_PARAM)
@@ -154,22 +136,101 @@ pub fn get_product_page_param(symbol: &str) -> &'static PageParam {
 struct Browser {
     port: u16,
     session: BrowserSession,
+    /// Прокси, установленный в браузере
+    proxy: std::sync::Mutex<Option<String>>,
+    /// Порт освобожден закрытием сессии
+    released: bool,
+}
+
+impl Browser {
+    fn proxy(&self) -> MutexGuard<'_, Option<String>> {
+        self.proxy.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Браузер, не закрытый `close()` (например, после паники задачи), освобождает порт.
+/// Процесс браузера завершается при удалении сессии.
+impl Drop for Browser {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+        let port = self.port;
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(async move { get_browser_states().stop_running(port).await });
+        }
+    }
 }
 
 #[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ReqMethod {
     Combined,
     Browser,
     Reqwest,
 }
 
+/// Контент страницы, HTTP статус ответа, если он известен,
+/// и классификация страницы по ответу
+#[derive(Debug)]
+pub struct Fetched {
+    pub content: String,
+    pub status: Option<u16>,
+    pub page_status: PageStatus,
+}
+
+/// Данные о товаре и диагностика их получения
+#[derive(Debug)]
+pub struct ProductResponse {
+    pub status: PageStatus,
+    pub data: Option<ProductData>,
+    pub diagnostics: ProductDiagnostics,
+    /// Количество попыток запроса по `RetryPolicy`
+    pub attempts: u32,
+}
+
+/// Результат пакетного запроса
+enum Batch {
+    /// Ответ, разделенный по товарам, и количество попыток
+    Split(Fetched, Vec<String>, FetchInfo, u32),
+    /// Запрос не удался после всех попыток: ответ с ошибкой для каждого товара пакета
+    Failed(PageStatus, FetchInfo, u32),
+}
+
+/// Профиль продавца и диагностика его получения
+#[derive(Debug)]
+pub struct SellerResponse {
+    pub status: PageStatus,
+    pub data: Option<SellerData>,
+    pub diagnostics: ProductDiagnostics,
+}
+
+/// Товары страницы выдачи и диагностика их получения
+#[derive(Debug)]
+pub struct ListingResponse {
+    pub status: PageStatus,
+    pub products: IndexMap<String, ProductData>,
+    pub diagnostics: ProductDiagnostics,
+}
+
 pub struct ReqSession {
-    browser: Option<Browser>,
-    req_client: Option<reqwest::Client>,
+    config: ReqSessionConfig,
+    /// Браузер, общий с копиями сессии
+    browser: Option<Arc<Browser>>,
+    /// Cookies клиентов reqwest, если метод запросов их использует
+    req_jar: Option<Arc<Jar>>,
+    /// Клиенты reqwest по прокси (без прокси - по пустой строке)
+    req_clients: HashMap<String, reqwest::Client>,
     proxy_pool: Vec<String>,
-    set_proxy_interval: u8,
+    proxy_map: HashMap<String, Vec<String>>,
+    /// Смена прокси, общая с копиями сессии
+    proxy_rotator: Arc<std::sync::Mutex<ProxyRotator>>,
+    /// Прокси последнего запроса и его время до классификации страницы
+    pending_proxy: Option<(String, Duration)>,
     close_tabs_interval: u16,
     req_count: usize,
+    characteristics: bool,
+    reviews: Option<OrderReviews>,
 }
 
 impl ReqSession {
@@ -178,8 +239,9 @@ impl ReqSession {
         method: ReqMethod,
         cookies: &Vec<OrderCookieParam>,
         proxy_pool: Vec<String>,
+        proxy_map: HashMap<String, Vec<String>>,
     ) -> Result<Self, ReqSessionError> {
-        let req_client = if matches!(method, ReqMethod::Reqwest | ReqMethod::Combined) {
+        let req_jar = if matches!(method, ReqMethod::Reqwest | ReqMethod::Combined) {
             let jar = Arc::new(Jar::default());
             for cookie in cookies {
                 let url = cookie.url.as_str().parse::<reqwest::Url>();
@@ -200,34 +262,7 @@ impl ReqSession {
                 jar.add_cookie_str(cookie_str.as_str(), &url.unwrap());
             }
 
-            let req_timings = &config.timings;
-            let mut req_client_builder = reqwest::Client::builder()
-                .user_agent(random_user_agent())
-                .cookie_provider(jar)
-                .timeout(Duration::from_millis(req_timings.timeout))
-                .connect_timeout(Duration::from_millis(req_timings.conn_timeout))
-                .read_timeout(Duration::from_millis(req_timings.read_timeout));
-
-            if !proxy_pool.is_empty() {
-                let proxy_str = proxy_pool.get(0).unwrap();
-                if let Ok(proxy_param) = ProxyParam::from_str(proxy_str) {
-                    let mut proxy =
-                        reqwest::Proxy::https(format!("http://{}", proxy_param.addrs()))
-                            .map_err(|_| ReqSessionError::BuildReqClient)?;
-                    if let (Some(username), Some(password)) =
-                        (proxy_param.username, proxy_param.password)
-                    {
-                        proxy = proxy.basic_auth(&username, &password);
-                    }
-                    req_client_builder = req_client_builder.proxy(proxy);
-                }
-            }
-
-            let req_client = req_client_builder
-                .build()
-                .map_err(|_| ReqSessionError::BuildReqClient)?;
-
-            Some(req_client)
+            Some(jar)
         } else {
             None
         };
@@ -266,11 +301,12 @@ impl ReqSession {
             let _ = browser.session.browser.clear_cookies().await;
             let _ = browser.session.reset_proxy().await;
             let _ = browser.session.browser.set_cookies(browser_cookies).await;
-            if !proxy_pool.is_empty() {
-                let _ = browser.session.set_proxy(&proxy_pool[0]).await;
+            if let Some(proxy) = proxy_pool.first() {
+                let _ = browser.session.set_proxy(proxy).await;
+                *browser.proxy() = Some(proxy.clone());
             }
 
-            Some(browser)
+            Some(Arc::new(browser))
         } else {
             None
         };
@@ -278,65 +314,612 @@ impl ReqSession {
         sleep(Duration::from_millis(config.launch_sleep)).await;
 
         Ok(Self {
+            config: config.clone(),
             browser,
-            req_client,
+            req_jar,
+            req_clients: HashMap::new(),
             proxy_pool,
-            set_proxy_interval: config.set_proxy_interval as u8,
+            proxy_map,
+            proxy_rotator: Arc::new(std::sync::Mutex::new(new_proxy_rotator(
+                config,
+                config.proxy_rotation,
+            ))),
+            pending_proxy: None,
             close_tabs_interval: config.close_tabs_interval as u16,
             req_count: 0,
+            characteristics: false,
+            reviews: None,
         })
     }
 
-    pub async fn req_product_data(
+    /// Дополнительно запрашивать характеристики товаров
+    pub fn with_characteristics(mut self, characteristics: bool) -> Self {
+        self.characteristics = characteristics;
+        self
+    }
+
+    /// Дополнительно запрашивать отзывы о товарах
+    pub fn with_reviews(mut self, reviews: Option<OrderReviews>) -> Self {
+        self.reviews = reviews;
+        self
+    }
+
+    /// Стратегия смены прокси заказа вместо стратегии из конфигурации
+    pub fn with_proxy_rotation(self, proxy_rotation: Option<ProxyRotation>) -> Self {
+        if let Some(proxy_rotation) = proxy_rotation {
+            *self.rotator() = new_proxy_rotator(&self.config, proxy_rotation);
+        }
+        self
+    }
+
+    /// Копия сессии для параллельных запросов товаров задачи: общие браузер (запросы
+    /// в отдельных вкладках), cookies и смена прокси, свои клиенты reqwest
+    pub fn fork(&self) -> Self {
+        Self {
+            config: self.config.clone(),
+            browser: self.browser.clone(),
+            req_jar: self.req_jar.clone(),
+            req_clients: HashMap::new(),
+            proxy_pool: self.proxy_pool.clone(),
+            proxy_map: self.proxy_map.clone(),
+            proxy_rotator: self.proxy_rotator.clone(),
+            pending_proxy: None,
+            close_tabs_interval: self.close_tabs_interval,
+            req_count: 0,
+            characteristics: self.characteristics,
+            reviews: self.reviews.clone(),
+        }
+    }
+
+    /// Количество одновременных запросов товаров маркетплейса в задаче.
+    /// Браузерный маркетплейс через прокси - по одному: прокси меняется для всего браузера.
+    pub fn parallelism(&self, symbol: Symbol) -> usize {
+        if self.uses_browser_proxy(symbol) {
+            return 1;
+        }
+        self.config
+            .parallelism
+            .get(symbol.as_str())
+            .copied()
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    /// Запросы маркетплейса идут через браузер с прокси
+    pub fn uses_browser_proxy(&self, symbol: Symbol) -> bool {
+        symbol.market().req_method() != ReqMethod::Reqwest && !self.proxy_pool(symbol).is_empty()
+    }
+
+    /// Количество товаров маркетплейса в одном пакетном запросе
+    pub fn batch_size(&self, symbol: Symbol) -> usize {
+        self.config
+            .batch_size
+            .get(symbol.as_str())
+            .copied()
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    fn rotator(&self) -> MutexGuard<'_, ProxyRotator> {
+        self.proxy_rotator.lock().unwrap()
+    }
+
+    /// Данные о товаре с повтором запроса после временной ошибки или капчи по `RetryPolicy`
+    pub async fn req_product_data(&mut self, product: &Product) -> ProductResponse {
+        let retry = self.config.retry.clone();
+        let mut attempts = 1;
+        loop {
+            let (mut response, transient) = self.req_product_data_attempt(product).await;
+            let retryable = transient
+                || (retry.retry_captcha && response.status == PageStatus::Captcha);
+            if !retryable || attempts >= retry.max_attempts {
+                response.attempts = attempts;
+                return response;
+            }
+            if retry.switch_proxy {
+                self.rotator().rotate(product.symbol.as_str());
+            }
+            sleep(retry_delay(&retry, attempts)).await;
+            attempts += 1;
+        }
+    }
+
+    /// Данные о товарах одного маркетплейса пакетным запросом, если маркетплейс
+    /// его поддерживает. Товары запрашиваются по одному, только если ответ на пакетный
+    /// запрос не удалось разделить по товарам. После временных ошибок на всех попытках
+    /// товары пакета получают ответ с ошибкой, чтобы не умножать запросы к маркетплейсу.
+    pub async fn req_products_data(&mut self, products: &[Product]) -> Vec<ProductResponse> {
+        let ids = products.iter().map(|p| p.id.as_str()).collect::<Vec<_>>();
+        let batch = match products {
+            [first, _, ..] => match first.symbol.market().batch_url(&ids) {
+                Some(url) => self.req_batch_contents(first.symbol, &ids, &url).await,
+                None => None,
+            },
+            _ => None,
+        };
+        let mut responses = Vec::with_capacity(products.len());
+        match batch {
+            Some(Batch::Split(batch_fetched, contents, fetch, attempts)) => {
+                for (product, content) in products.iter().zip(contents) {
+                    let fetched = Fetched {
+                        content,
+                        status: batch_fetched.status,
+                        page_status: batch_fetched.page_status,
+                    };
+                    let fetch = FetchInfo {
+                        bytes: fetched.content.len(),
+                        ..fetch.clone()
+                    };
+                    let (mut response, _) = self.product_response(product, fetched, fetch).await;
+                    response.attempts = attempts;
+                    responses.push(response);
+                }
+            }
+            Some(Batch::Failed(status, fetch, attempts)) => {
+                for _ in products {
+                    responses.push(ProductResponse {
+                        status,
+                        data: None,
+                        diagnostics: ProductDiagnostics {
+                            fetch: fetch.clone(),
+                            ..Default::default()
+                        },
+                        attempts,
+                    });
+                }
+            }
+            None => {
+                for product in products {
+                    responses.push(self.req_product_data(product).await);
+                }
+            }
+        }
+
+        responses
+    }
+
+    /// Ответ на пакетный запрос, разделенный по товарам, с повтором после временной ошибки
+    /// или капчи по `RetryPolicy`. `None`, если ответ не удалось разделить по товарам.
+    async fn req_batch_contents(
+        &mut self,
+        symbol: Symbol,
+        ids: &[&str],
+        url: &str,
+    ) -> Option<Batch> {
+        let retry = self.config.retry.clone();
+        let mut attempts = 1;
+        loop {
+            let started = Instant::now();
+            let fetched = self.fetch_content(symbol, url).await;
+            let mut fetch = FetchInfo {
+                status: fetched.as_ref().ok().and_then(|f| f.status),
+                elapsed_ms: started.elapsed().as_millis() as u64,
+                batch: Some(ids.len()),
+                ..Default::default()
+            };
+            let (transient, page_status) = match fetched {
+                Ok(fetched) => {
+                    let transient = fetch.status.is_some_and(|s| s == 429 || s >= 500);
+                    if !transient && fetched.page_status.is_ok() {
+                        let contents = symbol.market().split_batch(ids, &fetched.content)?;
+                        return Some(Batch::Split(fetched, contents, fetch, attempts));
+                    }
+                    self.mark_page_status(fetched.page_status);
+                    (transient, fetched.page_status)
+                }
+                Err(e) => {
+                    fetch.error = Some(e.to_string());
+                    (e.is_transient(), PageStatus::Ok)
+                }
+            };
+            let retryable =
+                transient || (retry.retry_captcha && page_status == PageStatus::Captcha);
+            if !retryable || attempts >= retry.max_attempts {
+                return Some(Batch::Failed(page_status, fetch, attempts));
+            }
+            if retry.switch_proxy {
+                self.rotator().rotate(symbol.as_str());
+            }
+            sleep(retry_delay(&retry, attempts)).await;
+            attempts += 1;
+        }
+    }
+
+    /// Данные о товаре и признак временной ошибки
+    async fn req_product_data_attempt(&mut self, product: &Product) -> (ProductResponse, bool) {
+        let started = Instant::now();
+        let fetched = self.fetch_product_content(product).await;
+        let mut fetch = FetchInfo {
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            ..Default::default()
+        };
+        match fetched {
+            Ok(fetched) => {
+                fetch.status = fetched.status;
+                fetch.bytes = fetched.content.len();
+                self.product_response(product, fetched, fetch).await
+            }
+            Err(e) => {
+                fetch.error = Some(e.to_string());
+
+                let response = ProductResponse {
+                    status: PageStatus::Ok,
+                    data: None,
+                    diagnostics: ProductDiagnostics {
+                        fetch,
+                        ..Default::default()
+                    },
+                    attempts: 1,
+                };
+                (response, e.is_transient())
+            }
+        }
+    }
+
+    /// Данные о товаре из ответа маркетплейса и признак временной ошибки
+    async fn product_response(
         &mut self,
         product: &Product,
-    ) -> Result<Option<ProductData>, ReqSessionError> {
-        if (self.req_count + 1) % self.close_tabs_interval as usize == 0 {
-            let _ = self.browser_close_tabs().await;
+        fetched: Fetched,
+        fetch: FetchInfo,
+    ) -> (ProductResponse, bool) {
+        let mut extraction = extract_data(product, &fetched.content);
+        self.req_count += 1;
+        if self.characteristics && extraction.data.is_some() {
+            match self.req_characteristics(product, &fetched.content).await {
+                Ok(characteristics) => {
+                    if let Some(pd) = extraction.data.as_mut() {
+                        pd.characteristics = Some(characteristics);
+                    }
+                }
+                Err(e) => {
+                    extraction.failures.insert("characteristics".into(), e);
+                }
+            }
         }
-        let url = product.get_parse_url();
-        let content = match product.symbol {
-            Symbol::OZ | Symbol::YM | Symbol::MM => {
-                let mut page_parsm = get_product_page_param(product.symbol.as_str()).clone();
-                if self.proxy_pool.len() > 1 {
-                    if (self.req_count + 1) % self.set_proxy_interval as usize == 0 {
-                        let index = (self.req_count / self.set_proxy_interval as usize)
-                            % self.proxy_pool.len();
-                        page_parsm.proxy = Some(&self.proxy_pool[index]);
+        if let (Some(params), true) = (self.reviews.clone(), extraction.data.is_some()) {
+            match self.req_reviews(product, &fetched.content, &params).await {
+                Ok(reviews) => {
+                    if let Some(pd) = extraction.data.as_mut() {
+                        pd.review_items = Some(reviews);
                     }
                 }
-                self.browser_get_content(&url, &page_parsm).await?
+                Err(e) => {
+                    extraction.failures.insert("reviews".into(), e);
+                }
+            }
+        }
+        // Признаки из правил точнее классификации по ответу,
+        // но полученные данные важнее любой классификации
+        let status = if extraction.data.is_some() {
+            PageStatus::Ok
+        } else if !extraction.status.is_ok() {
+            extraction.status
+        } else {
+            fetched.page_status
+        };
+        // Страница блокировки: следующий запрос через другой прокси
+        self.mark_page_status(status);
+        let transient = extraction.data.is_none()
+            && fetch.status.is_some_and(|s| s == 429 || s >= 500);
+
+        let response = ProductResponse {
+            status,
+            data: extraction.data,
+            diagnostics: ProductDiagnostics {
+                status,
+                fetch,
+                error: extraction.error,
+                fields: extraction.failures,
+            },
+            attempts: 1,
+        };
+        (response, transient)
+    }
+
+    pub async fn req_listing_page(&mut self, symbol: Symbol, url: &str) -> ListingResponse {
+        let started = Instant::now();
+        let fetched = self.fetch_content(symbol, url).await;
+        let mut fetch = FetchInfo {
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            ..Default::default()
+        };
+        let fetched = match fetched {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                fetch.error = Some(e.to_string());
+
+                return ListingResponse {
+                    status: PageStatus::Ok,
+                    products: IndexMap::new(),
+                    diagnostics: ProductDiagnostics {
+                        fetch,
+                        ..Default::default()
+                    },
+                };
+            }
+        };
+        fetch.status = fetched.status;
+        fetch.bytes = fetched.content.len();
+        self.req_count += 1;
+        let (listing, error) = match extract_listing(symbol, &fetched.content) {
+            Ok(listing) => (listing, None),
+            Err(e) => (Listing::default(), Some(e)),
+        };
+        let status = if !listing.products.is_empty() {
+            PageStatus::Ok
+        } else if !listing.status.is_ok() {
+            listing.status
+        } else {
+            fetched.page_status
+        };
+        self.mark_page_status(status);
+
+        ListingResponse {
+            status,
+            products: listing.products,
+            diagnostics: ProductDiagnostics {
+                status,
+                fetch,
+                error,
+                fields: listing.failures,
+            },
+        }
+    }
+
+    pub async fn req_seller_profile(&mut self, symbol: Symbol, seller_id: &str) -> SellerResponse {
+        let mut response = SellerResponse {
+            status: PageStatus::Ok,
+            data: None,
+            diagnostics: ProductDiagnostics::default(),
+        };
+        let Some(url) = symbol.market().seller_profile_url(seller_id) else {
+            response.diagnostics.error = Some("seller profile is not supported".into());
+            return response;
+        };
+        let started = Instant::now();
+        let fetched = self.fetch_content(symbol, &url).await;
+        let fetch = &mut response.diagnostics.fetch;
+        fetch.elapsed_ms = started.elapsed().as_millis() as u64;
+        let fetched = match fetched {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                fetch.error = Some(e.to_string());
+                return response;
             }
-            Symbol::WB => self.reqwest_get_content(&url).await?,
         };
-        let product_data = extract_data(product, &content);
+        fetch.status = fetched.status;
+        fetch.bytes = fetched.content.len();
+        self.req_count += 1;
+        let seller = match extract_seller(symbol, seller_id, &fetched.content) {
+            Ok(seller) => seller,
+            Err(e) => {
+                response.diagnostics.error = Some(e);
+                Seller::default()
+            }
+        };
+        let status = if seller.data.is_some() {
+            PageStatus::Ok
+        } else if !seller.status.is_ok() {
+            seller.status
+        } else {
+            fetched.page_status
+        };
+        self.mark_page_status(status);
+        response.status = status;
+        response.data = seller.data;
+        response.diagnostics.status = status;
+        response.diagnostics.fields = seller.failures;
+
+        response
+    }
+
+    /// Характеристики со страницы характеристик маркетплейса
+    /// или из основного контента, если отдельной страницы нет
+    async fn req_characteristics(
+        &mut self,
+        product: &Product,
+        content: &str,
+    ) -> Result<IndexMap<String, String>, String> {
+        match product.symbol.market().characteristics_url(&product.id) {
+            Some(url) => {
+                let fetched = self
+                    .fetch_content(product.symbol, &url)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                self.req_count += 1;
+                let characteristics = extract_characteristics(product, &fetched.content)?;
+                let status = if !characteristics.status.is_ok() {
+                    characteristics.status
+                } else {
+                    fetched.page_status
+                };
+                if characteristics.characteristics.is_none() && !status.is_ok() {
+                    self.mark_page_status(status);
+                    return Err(format!("{:?} page", status));
+                }
+                characteristics.values()
+            }
+            None => extract_characteristics(product, content)?.values(),
+        }
+    }
+
+    /// Отзывы со страниц отзывов по порядку до пустой страницы, `limit` отзывов
+    /// или страницы, на которой все отзывы раньше `since`. Ошибка на первой странице -
+    /// ошибка запроса, на следующих - конец отзывов.
+    async fn req_reviews(
+        &mut self,
+        product: &Product,
+        content: &str,
+        params: &OrderReviews,
+    ) -> Result<Vec<ReviewData>, String> {
+        let market = product.symbol.market();
+        let mut reviews = Vec::new();
+        for page in 1..=REVIEWS_PAGES_LIMIT {
+            let Some(url) = market.reviews_url(&product.id, content, page) else {
+                if page == 1 {
+                    return Err("reviews are not supported".into());
+                }
+                break;
+            };
+            let page_reviews = match self.req_reviews_page(product, &url).await {
+                Ok(page_reviews) => page_reviews,
+                Err(e) if page == 1 => return Err(e),
+                Err(_) => break,
+            };
+            if page_reviews.is_empty() {
+                break;
+            }
+            let mut newer = 0;
+            for review in page_reviews {
+                let older = match (&params.since, &review.date) {
+                    (Some(since), Some(date)) => date < since,
+                    _ => false,
+                };
+                if older {
+                    continue;
+                }
+                newer += 1;
+                reviews.push(review);
+                if reviews.len() as u64 >= params.limit {
+                    return Ok(reviews);
+                }
+            }
+            if newer == 0 {
+                break;
+            }
+        }
+
+        Ok(reviews)
+    }
+
+    async fn req_reviews_page(&mut self, product: &Product, url: &str) -> Result<Vec<ReviewData>, String> {
+        let fetched = self
+            .fetch_content(product.symbol, url)
+            .await
+            .map_err(|e| e.to_string())?;
         self.req_count += 1;
+        let reviews = extract_reviews(product, &fetched.content)?;
+        let status = if !reviews.status.is_ok() {
+            reviews.status
+        } else {
+            fetched.page_status
+        };
+        if reviews.reviews.is_empty() && !status.is_ok() {
+            self.mark_page_status(status);
+            return Err(format!("{:?} page", status));
+        }
+
+        Ok(reviews.reviews)
+    }
+
+    pub async fn fetch_product_content(&mut self, product: &Product) -> Result<Fetched, ReqSessionError> {
+        self.fetch_content(product.symbol, &product.get_parse_url())
+            .await
+    }
+
+    async fn fetch_content(&mut self, symbol: Symbol, url: &str) -> Result<Fetched, ReqSessionError> {
+        // Вкладки не закрываются, пока браузером пользуются копии сессии
+        let shared_browser = self.browser.as_ref().is_some_and(|b| Arc::strong_count(b) > 1);
+        if !shared_browser && (self.req_count + 1) % self.close_tabs_interval as usize == 0 {
+            let _ = self.browser_close_tabs().await;
+        }
+        // Контент предыдущего запроса не классифицирован как блокировка
+        self.settle_proxy(false);
+        let proxy_pool = self.proxy_pool(symbol).to_vec();
+        let proxy = if proxy_pool.is_empty() {
+            None
+        } else {
+            let proxy = self
+                .rotator()
+                .next(symbol.as_str(), &proxy_pool)
+                .ok_or(ReqSessionError::ProxyPoolExhausted)?;
+            Some(proxy)
+        };
+        limiter::acquire(symbol.as_str(), proxy.as_deref()).await;
+        let started = Instant::now();
+        let fetched = match symbol.market().req_method() {
+            ReqMethod::Browser | ReqMethod::Combined => {
+                let browser = self
+                    .browser
+                    .clone()
+                    .ok_or(ReqSessionError::NotAvailableReqMethod)?;
+                let mut page_parsm = get_product_page_param(symbol.as_str()).clone();
+                let browser_proxy = browser.proxy().clone();
+                if proxy.is_none() && browser_proxy.is_some() {
+                    // Маркетплейс без прокси после маркетплейса со своим пулом
+                    let _ = browser.session.reset_proxy().await;
+                    *browser.proxy() = None;
+                }
+                // Прокси меняется в браузере, только если он отличается от установленного
+                let set_proxy = proxy.clone().filter(|p| browser_proxy.as_ref() != Some(p));
+                page_parsm.proxy = set_proxy.as_deref();
+                let fetched = self.browser_get_content(url, &page_parsm).await;
+                if set_proxy.is_some() {
+                    *browser.proxy() = set_proxy;
+                }
+                fetched
+            }
+            ReqMethod::Reqwest => self.reqwest_get_content(proxy.as_ref(), url).await,
+        };
+        if let Some(proxy) = proxy {
+            match &fetched {
+                Ok(_) => self.pending_proxy = Some((proxy, started.elapsed())),
+                Err(_) => self.rotator().record_failure(&proxy, started.elapsed()),
+            }
+        }
+
+        fetched
+    }
 
-        Ok(product_data)
+    /// Классификация страницы последнего запроса: блокировка - неудачный запрос
+    /// через прокси, после которого прокси меняется по стратегии
+    fn mark_page_status(&mut self, status: PageStatus) {
+        self.settle_proxy(status.is_blocked());
+    }
+
+    fn settle_proxy(&mut self, blocked: bool) {
+        if let Some((proxy, latency)) = self.pending_proxy.take() {
+            if blocked {
+                self.rotator().record_failure(&proxy, latency);
+            } else {
+                self.rotator().record_success(&proxy, latency);
+            }
+        }
+    }
+
+    /// Статистика прокси за время сессии
+    pub fn proxy_stats(&mut self) -> IndexMap<String, ProxyStats> {
+        self.settle_proxy(false);
+        self.rotator().stats()
     }
 
     pub async fn browser_get_content(
         &self,
         url: &str,
         page_parsm: &PageParam<'_>,
-    ) -> Result<String, ReqSessionError> {
-        let page = self.browser_open_page(url, page_parsm).await?;
+    ) -> Result<Fetched, ReqSessionError> {
+        let (page, page_status) = self.browser_open_page(url, page_parsm).await?;
         let content = page.content().await.map_err(|e| BrowserError::from(e))?;
         let _ = page.close().await;
 
-        Ok(content)
+        Ok(Fetched {
+            content,
+            status: None,
+            page_status,
+        })
     }
 
     pub async fn browser_open_page(
         &self,
         url: &str,
         page_parsm: &PageParam<'_>,
-    ) -> Result<Page, ReqSessionError> {
+    ) -> Result<(Page, PageStatus), ReqSessionError> {
         self.browser
             .as_ref()
             .ok_or(ReqSessionError::NotAvailableReqMethod)?
             .session
-            .open_with_param(url, page_parsm)
+            .open_with_status(url, page_parsm)
             .await
             .map_err(|e| e.into())
     }
@@ -351,29 +934,118 @@ impl ReqSession {
             .map_err(|e| e.into())
     }
 
-    pub async fn reqwest_get_content(&self, url: &str) -> Result<String, ReqSessionError> {
-        self.req_client
+    /// Внешний IP браузера через прокси `proxy` по ответу сервиса `url`
+    pub async fn browser_myip(&mut self, proxy: &str, url: &str) -> Result<MyIP, ReqSessionError> {
+        let browser = self
+            .browser
             .as_ref()
-            .ok_or(ReqSessionError::NotAvailableReqMethod)?
+            .ok_or(ReqSessionError::NotAvailableReqMethod)?;
+        browser.session.set_proxy(proxy).await?;
+        *browser.proxy() = Some(proxy.into());
+
+        Ok(browser.session.myip_from(url).await?)
+    }
+
+    /// Пул прокси маркетплейса: из `proxy_map`, иначе общий
+    fn proxy_pool(&self, symbol: Symbol) -> &[String] {
+        self.proxy_map
+            .get(symbol.as_str())
+            .unwrap_or(&self.proxy_pool)
+    }
+
+    pub async fn reqwest_get_content(
+        &mut self,
+        proxy: Option<&String>,
+        url: &str,
+    ) -> Result<Fetched, ReqSessionError> {
+        let key = proxy.cloned().unwrap_or_default();
+        if !self.req_clients.contains_key(&key) {
+            let jar = self
+                .req_jar
+                .clone()
+                .ok_or(ReqSessionError::NotAvailableReqMethod)?;
+            let req_client = build_req_client(&self.config, jar, proxy)?;
+            self.req_clients.insert(key.clone(), req_client);
+        }
+        let response = self.req_clients[&key]
             .get(url)
             .send()
             .await
-            .map_err(|_| ReqSessionError::RequestSending)?
+            .map_err(|_| ReqSessionError::RequestSending)?;
+        let status = response.status().as_u16();
+        let content = response
             .text()
             .await
-            .map_err(|_| ReqSessionError::ExtractResponseContent)
+            .map_err(|_| ReqSessionError::ExtractResponseContent)?;
+
+        Ok(Fetched {
+            content,
+            status: Some(status),
+            page_status: PageStatus::from_http_status(status),
+        })
     }
 
+    /// Браузер закрывается последней из копий сессии
     pub async fn close(&mut self) {
-        if let Some(browser) = &mut self.browser {
+        if let Some(mut browser) = self.browser.take().and_then(Arc::into_inner) {
             browser.session.close().await;
             let browser_states = get_browser_states();
             browser_states.stop_running(browser.port).await;
+            browser.released = true;
         }
         self.req_count = 0;
+        self.pending_proxy = None;
     }
 }
 
+/// Задержка перед попыткой `attempt + 1`: удваивается с каждой попыткой
+/// и случайно уменьшается на долю `jitter`
+fn retry_delay(retry: &RetryPolicy, attempt: u32) -> Duration {
+    let delay = retry
+        .base_delay_ms
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(retry.max_delay_ms);
+    let jitter = retry.jitter.clamp(0.0, 1.0) * rand::random::<f64>();
+
+    Duration::from_millis((delay as f64 * (1.0 - jitter)) as u64)
+}
+
+fn new_proxy_rotator(config: &ReqSessionConfig, proxy_rotation: ProxyRotation) -> ProxyRotator {
+    ProxyRotator::new(
+        proxy_rotation,
+        config.set_proxy_interval,
+        ProxyHealth::new(config.proxy_health.clone()),
+    )
+}
+
+/// Клиент reqwest с общими cookies и прокси
+pub fn build_req_client(
+    config: &ReqSessionConfig,
+    jar: Arc<Jar>,
+    proxy: Option<&String>,
+) -> Result<reqwest::Client, ReqSessionError> {
+    let req_timings = &config.timings;
+    let mut req_client_builder = reqwest::Client::builder()
+        .user_agent(random_user_agent())
+        .cookie_provider(jar)
+        .timeout(Duration::from_millis(req_timings.timeout))
+        .connect_timeout(Duration::from_millis(req_timings.conn_timeout))
+        .read_timeout(Duration::from_millis(req_timings.read_timeout));
+
+    if let Some(proxy_str) = proxy {
+        if let Ok(proxy_param) = ProxyParam::from_str(proxy_str) {
+            // Учетные данные из ссылки: basic auth для HTTP, авторизация SOCKS5
+            let proxy = reqwest::Proxy::all(proxy_param.to_url())
+                .map_err(|_| ReqSessionError::BuildReqClient)?;
+            req_client_builder = req_client_builder.proxy(proxy);
+        }
+    }
+
+    req_client_builder
+        .build()
+        .map_err(|_| ReqSessionError::BuildReqClient)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -391,6 +1063,7 @@ mod tests {
             ReqMethod::Combined,
             &vec![],
             vec![],
+            HashMap::new(),
         )
         .await
         .unwrap();
@@ -430,6 +1103,53 @@ mod tests {
         assert_eq!(true, true);
     }
 
+    #[test]
+    fn test_retry_delay() {
+        let retry = RetryPolicy {
+            base_delay_ms: 500,
+            max_delay_ms: 3000,
+            jitter: 0.0,
+            ..Default::default()
+        };
+        let delays = (1..=5)
+            .map(|attempt| retry_delay(&retry, attempt).as_millis())
+            .collect::<Vec<_>>();
+        assert_eq!(delays, [500, 1000, 2000, 3000, 3000]);
+
+        let retry = RetryPolicy { jitter: 0.5, ..retry };
+        let delay = retry_delay(&retry, 2).as_millis();
+        assert!((500..=1000).contains(&delay));
+    }
+
+    #[tokio::test]
+    async fn test_parallelism() {
+        let config = ReqSessionConfig {
+            launch_sleep: 0,
+            parallelism: HashMap::from([("wb".into(), 4), ("oz".into(), 3)]),
+            ..Default::default()
+        };
+        let proxy = "http://127.0.0.1:8000".to_string();
+        let rs = ReqSession::new(
+            &config,
+            ReqMethod::Reqwest,
+            &vec![],
+            vec![],
+            HashMap::from([("oz".into(), vec![proxy.clone()])]),
+        )
+        .await
+        .unwrap();
+        let symbol = |s| Symbol::from_string(s).unwrap();
+        assert_eq!(rs.parallelism(symbol("wb")), 4);
+        assert_eq!(rs.parallelism(symbol("ym")), 1);
+        // Браузерный маркетплейс через прокси
+        assert_eq!(rs.parallelism(symbol("oz")), 1);
+
+        // Копии сессии меняют прокси вместе с сессией
+        let fork = rs.fork();
+        fork.rotator().record_success(&proxy, Duration::from_millis(100));
+        assert_eq!(rs.rotator().stats()[&proxy].successes, 1);
+    }
+
     #[tokio::test]
     async fn test_proxy_pool() {
         let proxy_pool: Vec<i32> = vec![1];
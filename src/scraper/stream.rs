@@ -1,6 +1,15 @@
-use std::sync::LazyLock;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, LazyLock,
+    },
+};
 
 use async_stream::stream;
+use browser_bridge::PageStatus;
+use indexmap::IndexSet;
+use tokio::task::JoinSet;
 use tokio_stream::Stream;
 
 use super::{
@@ -8,11 +17,11 @@ use super::{
         api::logger,
         config as cfg,
         models::{
-            api::{Task, TaskStatus},
-            scraper::Product,
+            api::{Order, Task, TaskResult, TaskStatus},
+            scraper::{Product, Symbol},
         },
     },
-    req::{ReqMethod, ReqSession},
+    req::{ProductResponse, ReqMethod, ReqSession},
 };
 
 static INTERRUPT_CHECK_STEP: LazyLock<u64> = LazyLock::new(|| cfg::get().api.interrupt_check_step);
@@ -46,16 +55,63 @@ static INTERRUPT_CHECK_STEP: LazyLock<u64> = LazyLock::new(|| cfg::get().api.int
 //     }
 // }
 
-pub async fn task_stream(mut task: Task) -> impl Stream<Item = Task> {
+/// Управление задачей из обработчика: отмена и приостановка выполняющейся задачи
+#[derive(Clone, Debug, Default)]
+pub struct TaskControl {
+    cancelled: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+}
+
+impl TaskControl {
+    /// Задача завершается со статусом `cancelled` после текущего товара
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Обработка товаров останавливается со статусом `paused` после текущего товара
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+}
+
+/// Поток состояний задачи. Задача с прогрессом (после паузы) продолжается
+/// с текущего шага с уже полученным результатом.
+pub async fn task_stream(mut task: Task, control: TaskControl) -> impl Stream<Item = Task> {
     //let mut skip_map = SkipMap::new();
     let intpt_check_step = *INTERRUPT_CHECK_STEP;
-    task.init_progress();
+    let resumed = task.progress.is_some();
+    if !resumed {
+        task.init_progress();
+    }
+    let order = task.order.clone();
     let order_data = task.extract_order_data();
-    let req_method = if order_data
-        .products
+    let listing_symbol = order_data
+        .listing
+        .as_ref()
+        .and_then(|listing| listing.market.as_deref())
+        .and_then(|symbol| Symbol::from_string(symbol).ok());
+    let mut order_items = order_data.products.clone();
+    let mut products = order_items
+        .iter()
+        .map(|p| Product::from_string_without_valid(p))
+        .collect::<Vec<_>>();
+    let req_method = if products
         .iter()
-        .find(|p| p.starts_with("oz") || p.starts_with("ym") || p.starts_with("mm"))
-        .is_some()
+        .map(|p| p.symbol)
+        .chain(listing_symbol)
+        .any(|symbol| symbol.market().req_method() != ReqMethod::Reqwest)
     {
         ReqMethod::Combined
     } else {
@@ -66,8 +122,15 @@ pub async fn task_stream(mut task: Task) -> impl Stream<Item = Task> {
         req_method,
         &order_data.cookies,
         order_data.proxy_pool,
+        order_data.proxy_map,
     )
-    .await;
+    .await
+    .map(|req_session| {
+        req_session
+            .with_characteristics(order_data.characteristics)
+            .with_reviews(order_data.reviews.clone())
+            .with_proxy_rotation(order_data.proxy_rotation)
+    });
     logger::write(
         if req_session_res.is_ok() {
             log::Level::Info
@@ -83,58 +146,230 @@ pub async fn task_stream(mut task: Task) -> impl Stream<Item = Task> {
         match req_session_res {
             Ok(mut req_session) => {
                 task.set_status(TaskStatus::Processing);
-                task.init_result_data();
-                if let Some(p) = order_data.products
+                if !resumed {
+                    task.init_result_data();
+                    if order_data.diagnostics {
+                        task.init_diagnostics();
+                    }
+                }
+                let mut failed_streak = 0;
+                let mut warmed_up = HashSet::new();
+                // Заказ выдачи: товары со страниц выдачи по порядку до пустой страницы или лимита
+                if let (Some(listing), Some(symbol)) = (order_data.listing.as_ref(), listing_symbol) {
+                    if symbol.market().warm_up() && warmed_up.insert(symbol) {
+                        if let Some(url) = listing.page_url(1) {
+                            let _ = req_session.req_listing_page(symbol, &url).await;
+                        }
+                    }
+                    if let (Some(seller_id), None) = (listing.seller_id(), task.seller.as_ref()) {
+                        let response = req_session.req_seller_profile(symbol, seller_id).await;
+                        task.seller = response.data;
+                        task.insert_diagnostics_item("seller".into(), response.diagnostics);
+                        task.insert_outcome("seller".into(), response.status);
+                    }
+                    // После паузы выдача продолжается со следующей страницы с найденными товарами
+                    let mut discovered = IndexSet::new();
+                    if let Some(TaskResult::Data(data)) = &task.result {
+                        discovered.extend(data.keys().cloned());
+                    }
+                    for page in task.get_curr_step() + 1..=listing.pages {
+                        if control.is_cancelled() {
+                            task.set_status(TaskStatus::Cancelled);
+                            yield task.clone();
+                            break;
+                        }
+                        if control.is_paused() {
+                            task.order = order.clone();
+                            task.set_status(TaskStatus::Paused);
+                            yield task.clone();
+                            break;
+                        }
+                        let Some(url) = listing.page_url(page) else {
+                            break;
+                        };
+                        let response = req_session.req_listing_page(symbol, &url).await;
+                        let found = response.products.len();
+                        for (order_item, mut pd) in response.products {
+                            if discovered.len() as u64 >= listing.limit {
+                                break;
+                            }
+                            if !discovered.insert(order_item.clone()) {
+                                continue;
+                            }
+                            if !order_data.extended {
+                                pd.strip_extended();
+                            }
+                            task.insert_result_item(order_item, Some(pd));
+                        }
+                        task.insert_diagnostics_item(url.clone(), response.diagnostics);
+                        task.insert_outcome(url, response.status);
+                        task.next_progress_step();
+                        task.proxies = req_session.proxy_stats();
+                        yield task.clone();
+                        if found == 0 || discovered.len() as u64 >= listing.limit {
+                            break;
+                        }
+                    }
+                    // Отмененная задача завершается с найденными товарами
+                    if !task.is_done_by_status() && task.status != TaskStatus::Paused {
+                        if listing.scrape && !discovered.is_empty() {
+                            order_items = discovered.into_iter().collect();
+                            products = order_items
+                                .iter()
+                                .map(|p| Product::from_string_without_valid(p))
+                                .collect();
+                            task.set_progress(0, products.len() as u64);
+                        } else {
+                            task.set_status(TaskStatus::Completed);
+                            yield task.clone();
+                        }
+                    }
+                }
+                for product in products
                     .iter()
-                    .find(|p| p.starts_with("oz"))
+                    .filter(|p| !task.is_done_by_status() && p.symbol.market().warm_up())
                 {
-                    let product = Product::from_string_without_valid(p);
-                    let _ = req_session
-                        .req_product_data(&product)
-                        .await;
+                    if warmed_up.insert(product.symbol) {
+                        let _ = req_session
+                            .req_product_data(product)
+                            .await;
+                    }
                 }
-                while !task.is_done_by_status() {
-                    let step = task.get_curr_step();
-                    let order_item = order_data.products[step as usize].clone();
-                    //if skip_map.is_skipped(&order_item) {
-                    //    task.insert_result_item(order_item, None)
-                    //} else {
-                    let product = Product::from_string_without_valid(&order_item);
-                    let product_data = req_session
-                        .req_product_data(&product)
-                        .await;
-                    match product_data {
-                        Ok(product_result) =>
-                            task.insert_result_item(order_item, product_result),
-                        Err(_) => task.insert_result_item(order_item, None)
+                // Товары запрашиваются параллельно копиями сессии в пределах лимита маркетплейса,
+                // результаты добавляются в задачу в порядке заказа
+                let parallelism = products
+                    .iter()
+                    .map(|p| (p.symbol, req_session.parallelism(p.symbol)))
+                    .collect::<HashMap<_, _>>();
+                let workers_count = parallelism
+                    .values()
+                    .copied()
+                    .max()
+                    .unwrap_or(1)
+                    .min(products.len().max(1));
+                // Браузер с прокси обрабатывает один товар: прокси меняется для всего браузера
+                let browser_exclusive = products
+                    .iter()
+                    .any(|p| req_session.uses_browser_proxy(p.symbol));
+                let batches = batches(&products, task.get_curr_step() as usize, |symbol| {
+                    req_session.batch_size(symbol)
+                });
+                let mut idle = (1..workers_count)
+                    .map(|_| req_session.fork())
+                    .collect::<Vec<_>>();
+                idle.push(req_session);
+                let mut jobs = JoinSet::new();
+                let mut in_flight = HashMap::<Symbol, usize>::new();
+                let mut ready = BTreeMap::<usize, ProductResponse>::new();
+                let mut next = 0;
+                while !task.is_done_by_status() && task.status != TaskStatus::Paused {
+                    if control.is_cancelled() {
+                        task.set_status(TaskStatus::Cancelled);
+                        yield task.clone();
+                        break;
                     }
-                    //}
-                    task.next_progress_step();
-                    let step = task.get_curr_step();
-                    if step >= intpt_check_step && step % intpt_check_step == 0 {
-                        if let Some(data) = task.extract_result_data() {
-                            if data.values().skip(data.len() - intpt_check_step as usize)
-                                .all(|v| v.is_none())
-                            {
-                                task.set_status(TaskStatus::Interrupted);
-                            }
+                    if control.is_paused() {
+                        // Заказ с товарами выдачи, чтобы продолжить с текущего шага без выдачи
+                        task.order = Order {
+                            products: order_items.clone(),
+                            listing: None,
+                            ..order.clone()
+                        };
+                        task.set_status(TaskStatus::Paused);
+                        yield task.clone();
+                        break;
+                    }
+                    while next < batches.len() && !idle.is_empty() {
+                        let steps = batches[next].clone();
+                        let symbol = products[steps[0]].symbol;
+                        let busy = in_flight.get(&symbol).copied().unwrap_or(0);
+                        let browser_busy = browser_exclusive
+                            && symbol.market().req_method() != ReqMethod::Reqwest
+                            && in_flight
+                                .iter()
+                                .any(|(s, n)| *n > 0 && s.market().req_method() != ReqMethod::Reqwest);
+                        if busy >= parallelism[&symbol] || browser_busy {
+                            break;
                         }
+                        let Some(mut worker) = idle.pop() else {
+                            break;
+                        };
+                        let batch = steps
+                            .iter()
+                            .map(|step| products[*step].clone())
+                            .collect::<Vec<_>>();
+                        jobs.spawn(async move {
+                            let responses = worker.req_products_data(&batch).await;
+                            (steps, worker, responses)
+                        });
+                        *in_flight.entry(symbol).or_default() += 1;
+                        next += 1;
                     }
-                    if task.is_done_by_progress() {
-                        task.set_status(TaskStatus::Completed);
+                    let (steps, mut worker, responses) = match jobs.join_next().await {
+                        Some(Ok(joined)) => joined,
+                        Some(Err(e)) => std::panic::resume_unwind(e.into_panic()),
+                        None => break,
+                    };
+                    if let Some(busy) = in_flight.get_mut(&products[steps[0]].symbol) {
+                        *busy -= 1;
+                    }
+                    task.proxies = worker.proxy_stats();
+                    idle.push(worker);
+                    ready.extend(steps.into_iter().zip(responses));
+                    while let Some(mut response) = ready.remove(&(task.get_curr_step() as usize)) {
+                        let order_item = order_items[task.get_curr_step() as usize].clone();
+                        //if skip_map.is_skipped(&order_item) {
+                        //    task.insert_result_item(order_item, None)
+                        //} else {
+                        if !order_data.extended {
+                            if let Some(pd) = response.data.as_mut() {
+                                pd.strip_extended();
+                            }
+                        }
+                        // Отсутствующий товар не признак блокировки
+                        if response.data.is_some() || response.status == PageStatus::NotFound {
+                            failed_streak = 0;
+                        } else {
+                            failed_streak += 1;
+                        }
+                        task.insert_diagnostics_item(order_item.clone(), response.diagnostics);
+                        task.insert_outcome(order_item.clone(), response.status);
+                        task.insert_attempts(order_item.clone(), response.attempts);
+                        task.insert_result_item(order_item, response.data);
+                        //}
+                        task.next_progress_step();
+                        if failed_streak >= intpt_check_step {
+                            task.set_status(TaskStatus::Interrupted);
+                        }
+                        if task.is_done_by_progress() {
+                            task.set_status(TaskStatus::Completed);
+                        }
+                        yield task.clone();
+                        if task.is_done_by_status() {
+                            break;
+                        }
                     }
-                    yield task.clone();
                 }
+                // Запросы товаров после прерывания задачи не нужны
+                jobs.shutdown().await;
                 task.result = None;
+                let summary = task.take_diagnostics_summary();
+                let mut message = serde_json::to_string(&task).unwrap_or_default();
+                if let Some(summary) = summary {
+                    message.push_str(" diagnostics: ");
+                    message.push_str(&serde_json::to_string(&summary).unwrap_or_default());
+                }
                 logger::write(
                     if !matches!(
                         task.status, TaskStatus::Error | TaskStatus::Interrupted
                         ) {log::Level::Info}
                         else {log::Level::Error},
                     "TASK_STREAM_END",
-                    serde_json::to_string(&task).unwrap_or_default()
+                    message
                 ).await;
-                req_session.close().await;
+                for mut worker in idle {
+                    worker.close().await;
+                }
             },
             Err(e) => {
                 task.set_status(TaskStatus::Error);
@@ -147,8 +382,59 @@ pub async fn task_stream(mut task: Task) -> impl Stream<Item = Task> {
     Box::pin(stream)
 }
 
+/// Пакеты товаров начиная с `start`: товары маркетплейса по порядку до `batch_size`
+/// в пакете. Пакеты упорядочены по первому товару.
+fn batches(
+    products: &[Product],
+    start: usize,
+    batch_size: impl Fn(Symbol) -> usize,
+) -> Vec<Vec<usize>> {
+    let mut batches: Vec<Vec<usize>> = Vec::new();
+    let mut open = HashMap::<Symbol, usize>::new();
+    for (step, product) in products.iter().enumerate().skip(start) {
+        match open.get(&product.symbol) {
+            Some(&i) if batches[i].len() < batch_size(product.symbol) => batches[i].push(step),
+            _ => {
+                open.insert(product.symbol, batches.len());
+                batches.push(vec![step]);
+            }
+        }
+    }
+
+    batches
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_control() {
+        let control = TaskControl::default();
+        // Обработчик и поток задачи пользуются общим состоянием
+        let stream_control = control.clone();
+        control.pause();
+        assert!(stream_control.is_paused());
+        control.resume();
+        assert!(!stream_control.is_paused());
+        assert!(!stream_control.is_cancelled());
+        control.cancel();
+        assert!(stream_control.is_cancelled());
+    }
+
+    #[test]
+    fn test_batches() {
+        let products = ["wb/1", "oz/2", "wb/3", "wb/4", "ym/5-6-7", "wb/8"]
+            .map(Product::from_string_without_valid);
+        let batch_size = |symbol: Symbol| if symbol.as_str() == "wb" { 2 } else { 1 };
+        assert_eq!(
+            batches(&products, 0, batch_size),
+            [vec![0, 2], vec![1], vec![3, 5], vec![4]]
+        );
+        assert_eq!(batches(&products, 3, batch_size), [vec![3, 5], vec![4]]);
+        assert_eq!(batches(&products, 0, |_| 1).len(), products.len());
+    }
+
     #[tokio::test]
     async fn test_task_stream() {
         let products = vec![